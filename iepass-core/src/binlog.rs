@@ -0,0 +1,232 @@
+use core::fmt;
+use embedded_io::Read;
+use crate::crc32::crc32;
+
+
+pub const MAGIC: u8 = 0xB7;
+pub const ESCAPE: u8 = 0xDB;
+pub const MAX_ARGS: usize = 4;
+// id, argument count, arguments and CRC before escaping
+const MAX_BODY_LEN: usize = 3 + MAX_ARGS * 4 + 4;
+pub const MAX_RECORD_LEN: usize = 1 + MAX_BODY_LEN * 2;
+
+// Format strings live only in this table, the device sends just the id and the arguments
+pub struct Tag {
+    pub id: u16,
+    pub format: &'static str,
+}
+
+pub mod tags {
+    use super::Tag;
+
+    pub const PLAYER_FRAME_LATE: Tag = Tag { id: 1, format: "player: frame {} late" };
+    pub const PLAYER_DONE: Tag = Tag { id: 2, format: "player: {} frames, {} dropped, {} us average busy" };
+
+    pub const ALL: &[Tag] = &[PLAYER_FRAME_LATE, PLAYER_DONE];
+}
+
+pub fn lookup(id: u16) -> Option<&'static Tag> {
+    tags::ALL.iter().find(|tag| tag.id == id)
+}
+
+// Bytes that never appear as is inside a record. The magic so a reader can always resync on it, CR and
+// LF so a console that translates line endings can't corrupt a record.
+fn needs_escape(byte: u8) -> bool {
+    matches!(byte, MAGIC | ESCAPE | b'\n' | b'\r')
+}
+
+// Record layout: MAGIC, then id (u16 LE), argument count, arguments (u32 LE each) and the CRC-32 of
+// all that (u32 LE), with every byte from needs_escape sent as ESCAPE, byte ^ 0x20
+pub fn encode(tag: &Tag, args: &[u32], out: &mut [u8; MAX_RECORD_LEN]) -> usize {
+    let args = &args[..args.len().min(MAX_ARGS)];
+
+    let mut body = [0; MAX_BODY_LEN];
+    body[0..2].copy_from_slice(&tag.id.to_le_bytes());
+    body[2] = args.len() as u8;
+    for (i, arg) in args.iter().enumerate() {
+        body[3 + i * 4..7 + i * 4].copy_from_slice(&arg.to_le_bytes());
+    }
+    let len = 3 + args.len() * 4;
+    let crc = crc32(&body[..len]);
+    body[len..len + 4].copy_from_slice(&crc.to_le_bytes());
+
+    out[0] = MAGIC;
+    let mut pos = 1;
+    for &byte in &body[..len + 4] {
+        if needs_escape(byte) {
+            out[pos..pos + 2].copy_from_slice(&[ESCAPE, byte ^ 0x20]);
+            pos += 2;
+        } else {
+            out[pos] = byte;
+            pos += 1;
+        }
+    }
+
+    pos
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Record {
+    pub id: u16,
+    argc: u8,
+    args: [u32; MAX_ARGS],
+}
+
+impl Record {
+    pub fn args(&self) -> &[u32] {
+        &self.args[..self.argc as usize]
+    }
+}
+
+impl fmt::Display for Record {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Some(tag) = lookup(self.id) else {
+            return write!(f, "<unknown tag {}> {:?}", self.id, self.args());
+        };
+
+        let mut args = self.args().iter();
+        let mut parts = tag.format.split("{}");
+
+        if let Some(first) = parts.next() {
+            f.write_str(first)?;
+        }
+        for part in parts {
+            match args.next() {
+                Some(arg) => write!(f, "{arg}")?,
+                None => f.write_str("?")?,
+            }
+            f.write_str(part)?;
+        }
+
+        Ok(())
+    }
+}
+
+// Reads records from a stream that may have text logs interleaved, anything outside a record is skipped,
+// and so are records cut short by a new one or a line break and records that fail their CRC
+pub struct RecordReader<R> {
+    reader: R,
+    // The last record was cut short by the magic of the next one
+    at_magic: bool,
+}
+
+impl<R: Read> RecordReader<R> {
+    pub fn new(reader: R) -> RecordReader<R> {
+        RecordReader { reader, at_magic: false }
+    }
+
+    pub fn next_record(&mut self) -> Result<Option<Record>, R::Error> {
+        let mut body = [0; MAX_BODY_LEN];
+        let mut byte = [0; 1];
+
+        'search: loop {
+            while !self.at_magic {
+                match self.reader.read(&mut byte)? {
+                    0 => return Ok(None),
+                    _ => self.at_magic = byte[0] == MAGIC,
+                }
+            }
+            self.at_magic = false;
+
+            let mut len = 0;
+            let mut escaped = false;
+            loop {
+                if self.reader.read(&mut byte)? == 0 {
+                    return Ok(None);
+                }
+
+                match byte[0] {
+                    MAGIC => {
+                        self.at_magic = true;
+                        continue 'search;
+                    }
+                    b'\n' | b'\r' => continue 'search,
+                    ESCAPE if !escaped => {
+                        escaped = true;
+                        continue;
+                    }
+                    byte => {
+                        body[len] = if escaped { byte ^ 0x20 } else { byte };
+                        escaped = false;
+                        len += 1;
+                    }
+                }
+
+                if len < 3 {
+                    continue;
+                }
+                let argc = body[2] as usize;
+                if argc > MAX_ARGS {
+                    continue 'search;
+                }
+                if len == 3 + argc * 4 + 4 {
+                    break;
+                }
+            }
+
+            let (data, crc) = body[..len].split_at(len - 4);
+            if crc32(data).to_le_bytes() != crc {
+                continue;
+            }
+
+            let mut record = Record {
+                id: u16::from_le_bytes([data[0], data[1]]),
+                argc: data[2],
+                args: [0; MAX_ARGS],
+            };
+            for (arg, bytes) in record.args.iter_mut().zip(data[3..].chunks_exact(4)) {
+                *arg = u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+            }
+
+            return Ok(Some(record));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::string::ToString;
+    use std::vec::Vec;
+
+    #[test]
+    fn test_binlog() {
+        let mut stream = Vec::new();
+        let mut record = [0; MAX_RECORD_LEN];
+
+        stream.extend_from_slice(b"I (123) iepass: Hello, world!\n");
+        let len = encode(&tags::PLAYER_FRAME_LATE, &[42], &mut record);
+        stream.extend_from_slice(&record[..len]);
+        stream.extend_from_slice(b"more text\n");
+        let len = encode(&tags::PLAYER_DONE, &[2191, 3, 31000, 7, 8], &mut record);
+        stream.extend_from_slice(&record[..len]);
+        let len = encode(&Tag { id: 999, format: "" }, &[1], &mut record);
+        stream.extend_from_slice(&record[..len]);
+
+        // Line endings and the magic in the payload are escaped, so a console turning LF into CRLF can't touch it
+        let len = encode(&tags::PLAYER_FRAME_LATE, &[0x0D0A_B70A], &mut record);
+        assert!(!record[1..len].iter().any(|&byte| matches!(byte, b'\n' | b'\r' | MAGIC)));
+        stream.extend_from_slice(&record[..len]);
+        // Corrupted records fail their CRC, ones cut short by text are dropped too
+        let len = encode(&tags::PLAYER_FRAME_LATE, &[5], &mut record);
+        record[5] ^= 1;
+        stream.extend_from_slice(&record[..len]);
+        stream.extend_from_slice(&record[..4]);
+        stream.extend_from_slice(b"\nI (456) iepass: interrupted\n");
+        stream.extend_from_slice(&[MAGIC, 1, 0]);
+        let stream: Vec<u8> = stream.into_iter().flat_map(|byte| if byte == b'\n' { std::vec![b'\r', b'\n'] } else { std::vec![byte] }).collect();
+
+        let mut reader = RecordReader::new(&stream[..]);
+        let mut decoded = Vec::new();
+        while let Some(record) = reader.next_record().unwrap() {
+            decoded.push(record.to_string());
+        }
+
+        assert_eq!(decoded, [
+            "player: frame 42 late",
+            "player: 2191 frames, 3 dropped, 31000 us average busy",
+            "<unknown tag 999> [1]",
+            "player: frame 218806026 late",
+        ]);
+    }
+}
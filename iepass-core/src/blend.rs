@@ -0,0 +1,38 @@
+// Cross-fading for low FPS videos: the player shows every frame in a few steps, each one a mix of the
+// previous frame and the new one, so slideshows and GIF converts don't jump on a faster panel.
+
+// Mixes two grayscale frames into `out`, `alpha` 0 is all `from` and 255 all `to`
+pub fn mix(from: &[u8], to: &[u8], alpha: u8, out: &mut [u8]) {
+    let alpha = alpha as u16;
+
+    for ((out, &from), &to) in out.iter_mut().zip(from).zip(to) {
+        *out = ((from as u16 * (255 - alpha) + to as u16 * alpha + 127) / 255) as u8;
+    }
+}
+
+// How many steps each frame of a `fps` video is shown in to get close to `target_fps`, 1 if it is
+// already at least that fast
+pub fn steps(fps: u8, target_fps: u8) -> u32 {
+    (target_fps / fps.max(1)).max(1) as u32
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_blend() {
+        let (from, to) = ([0, 255, 100, 7], [255, 0, 100, 9]);
+        let mut out = [0; 4];
+
+        mix(&from, &to, 0, &mut out);
+        assert_eq!(out, from);
+        mix(&from, &to, 255, &mut out);
+        assert_eq!(out, to);
+        mix(&from, &to, 128, &mut out);
+        assert_eq!(out, [128, 127, 100, 8]);
+
+        assert_eq!([1, 10, 12, 30, 60, 0].map(|fps| steps(fps, 30)), [30, 3, 2, 1, 1, 30]);
+    }
+}
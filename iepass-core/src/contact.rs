@@ -0,0 +1,194 @@
+use core::fmt;
+
+
+// Compact binary contact card: a version byte followed by (tag, length, UTF-8 bytes) fields.
+// Unknown tags are skipped, so newer cards still load on older firmware.
+pub const VERSION: u8 = 1;
+// Leaves room for a header in a 250 byte ESP-NOW frame
+pub const MAX_LEN: usize = 200;
+
+const TAG_NAME: u8 = 1;
+const TAG_ORG: u8 = 2;
+const TAG_PHONE: u8 = 3;
+const TAG_EMAIL: u8 = 4;
+const TAG_URL: u8 = 5;
+const TAG_NOTE: u8 = 6;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Error {
+    TooLong,
+    Truncated,
+    UnsupportedVersion(u8),
+    InvalidUtf8,
+    MissingName,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Contact<'a> {
+    pub name: &'a str,
+    pub org: Option<&'a str>,
+    pub phone: Option<&'a str>,
+    pub email: Option<&'a str>,
+    pub url: Option<&'a str>,
+    pub note: Option<&'a str>,
+}
+
+impl<'a> Contact<'a> {
+    pub fn new(name: &'a str) -> Contact<'a> {
+        Contact {
+            name,
+            ..Contact::default()
+        }
+    }
+
+    fn fields(&self) -> [(u8, Option<&'a str>); 6] {
+        [
+            (TAG_NAME, Some(self.name)),
+            (TAG_ORG, self.org),
+            (TAG_PHONE, self.phone),
+            (TAG_EMAIL, self.email),
+            (TAG_URL, self.url),
+            (TAG_NOTE, self.note),
+        ]
+    }
+
+    pub fn encode(&self, output: &mut [u8]) -> Result<usize, Error> {
+        let limit = output.len().min(MAX_LEN);
+        let output = &mut output[..limit];
+        let mut len = 1;
+
+        *output.first_mut().ok_or(Error::TooLong)? = VERSION;
+
+        for (tag, value) in self.fields() {
+            let Some(value) = value else { continue };
+            let bytes = value.as_bytes();
+
+            if bytes.len() > u8::MAX as usize || len + 2 + bytes.len() > output.len() {
+                return Err(Error::TooLong);
+            }
+
+            output[len] = tag;
+            output[len + 1] = bytes.len() as u8;
+            output[len + 2..len + 2 + bytes.len()].copy_from_slice(bytes);
+            len += 2 + bytes.len();
+        }
+
+        Ok(len)
+    }
+
+    pub fn decode(input: &'a [u8]) -> Result<Contact<'a>, Error> {
+        let (&version, mut rest) = input.split_first().ok_or(Error::Truncated)?;
+        if version != VERSION {
+            return Err(Error::UnsupportedVersion(version));
+        }
+
+        let mut name = None;
+        let mut contact = Contact::default();
+
+        while let [tag, len, tail @ ..] = rest {
+            let value = tail.get(..*len as usize).ok_or(Error::Truncated)?;
+            let value = core::str::from_utf8(value).map_err(|_| Error::InvalidUtf8)?;
+            rest = &tail[*len as usize..];
+
+            match *tag {
+                TAG_NAME => name = Some(value),
+                TAG_ORG => contact.org = Some(value),
+                TAG_PHONE => contact.phone = Some(value),
+                TAG_EMAIL => contact.email = Some(value),
+                TAG_URL => contact.url = Some(value),
+                TAG_NOTE => contact.note = Some(value),
+                _ => {}
+            }
+        }
+
+        if !rest.is_empty() {
+            return Err(Error::Truncated);
+        }
+
+        contact.name = name.ok_or(Error::MissingName)?;
+        Ok(contact)
+    }
+
+    // Formats the card as a vCard 3.0, for QR export
+    pub fn vcard(&self) -> VCard<'_, 'a> {
+        VCard(self)
+    }
+}
+
+pub struct VCard<'c, 'a>(&'c Contact<'a>);
+
+struct Escaped<'a>(&'a str);
+
+impl fmt::Display for Escaped<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for c in self.0.chars() {
+            match c {
+                '\\' | ',' | ';' => write!(f, "\\{c}")?,
+                '\n' => f.write_str("\\n")?,
+                '\r' => {}
+                c => write!(f, "{c}")?,
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl fmt::Display for VCard<'_, '_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let contact = self.0;
+
+        write!(f, "BEGIN:VCARD\r\nVERSION:3.0\r\nFN:{}\r\n", Escaped(contact.name))?;
+
+        let properties = [
+            ("ORG", contact.org),
+            ("TEL", contact.phone),
+            ("EMAIL", contact.email),
+            ("URL", contact.url),
+            ("NOTE", contact.note),
+        ];
+        for (name, value) in properties {
+            if let Some(value) = value {
+                write!(f, "{name}:{}\r\n", Escaped(value))?;
+            }
+        }
+
+        write!(f, "END:VCARD\r\n")
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_contact() {
+        let contact = Contact {
+            phone: Some("+48 123 456 789"),
+            note: Some("Met at the meetup; say hi"),
+            ..Contact::new("Zażółć Gęślą")
+        };
+
+        let mut buf = [0; MAX_LEN];
+        let len = contact.encode(&mut buf).unwrap();
+        assert_eq!(len, 1 + 2 + 19 + 2 + 15 + 2 + 25);
+        assert_eq!(Contact::decode(&buf[..len]), Ok(contact));
+
+        // Unknown fields from a future version are skipped
+        let mut extended = [0; MAX_LEN];
+        extended[..len].copy_from_slice(&buf[..len]);
+        extended[len..len + 4].copy_from_slice(&[42, 2, b'h', b'i']);
+        assert_eq!(Contact::decode(&extended[..len + 4]), Ok(contact));
+
+        assert_eq!(Contact::decode(&buf[..len - 1]), Err(Error::Truncated));
+        assert_eq!(Contact::decode(&[2, 1, 1, b'x']), Err(Error::UnsupportedVersion(2)));
+        assert_eq!(Contact::decode(&[VERSION, TAG_PHONE, 1, b'1']), Err(Error::MissingName));
+        assert_eq!(Contact::new(&"x".repeat(300)).encode(&mut buf), Err(Error::TooLong));
+
+        assert_eq!(
+            contact.vcard().to_string(),
+            "BEGIN:VCARD\r\nVERSION:3.0\r\nFN:Zażółć Gęślą\r\nTEL:+48 123 456 789\r\nNOTE:Met at the meetup\\; say hi\r\nEND:VCARD\r\n",
+        );
+    }
+}
@@ -0,0 +1,176 @@
+use core::fmt;
+use core::str::FromStr;
+
+
+pub const DICE: [u8; 7] = [4, 6, 8, 10, 12, 20, 100];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Roll {
+    pub count: u8,
+    pub sides: u8,
+    pub modifier: i16,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParseRollError;
+
+impl Roll {
+    pub fn new(count: u8, sides: u8) -> Roll {
+        Roll {
+            count,
+            sides,
+            modifier: 0,
+        }
+    }
+
+    pub fn with_modifier(mut self, modifier: i16) -> Roll {
+        self.modifier = modifier;
+        self
+    }
+
+    // `rng` is expected to return uniformly distributed u32s, eg. esp_random()
+    pub fn roll(&self, mut rng: impl FnMut() -> u32) -> i32 {
+        let sides = self.sides.max(1) as u32;
+        // Reject the top of the range so all faces are equally likely
+        let zone = u32::MAX - u32::MAX % sides;
+
+        let mut total = self.modifier as i32;
+        for _ in 0..self.count {
+            let value = loop {
+                let value = rng();
+                if value < zone {
+                    break value;
+                }
+            };
+            total += (value % sides) as i32 + 1;
+        }
+
+        total
+    }
+
+    pub fn min(&self) -> i32 {
+        self.count as i32 + self.modifier as i32
+    }
+
+    pub fn max(&self) -> i32 {
+        self.count as i32 * self.sides as i32 + self.modifier as i32
+    }
+}
+
+impl FromStr for Roll {
+    type Err = ParseRollError;
+
+    // Parses `[count]d<sides>[+|-modifier]`, eg. "d20", "3d6+2"
+    fn from_str(s: &str) -> Result<Roll, ParseRollError> {
+        let (count, rest) = s.trim().split_once(['d', 'D']).ok_or(ParseRollError)?;
+        let count = if count.is_empty() { 1 } else { count.parse().map_err(|_| ParseRollError)? };
+
+        let (sides, modifier) = match rest.find(['+', '-']) {
+            Some(pos) => (&rest[..pos], rest[pos..].parse().map_err(|_| ParseRollError)?),
+            None => (rest, 0),
+        };
+        let sides = sides.parse().map_err(|_| ParseRollError)?;
+
+        if count == 0 || sides == 0 {
+            return Err(ParseRollError);
+        }
+
+        Ok(Roll::new(count, sides).with_modifier(modifier))
+    }
+}
+
+impl fmt::Display for Roll {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}d{}", self.count, self.sides)?;
+
+        if self.modifier != 0 {
+            write!(f, "{:+}", self.modifier)?;
+        }
+
+        Ok(())
+    }
+}
+
+pub struct History<const N: usize> {
+    entries: [(Roll, i32); N],
+    len: usize,
+    next: usize,
+}
+
+impl<const N: usize> History<N> {
+    pub fn new() -> Self {
+        History {
+            entries: [(Roll::new(0, 0), 0); N],
+            len: 0,
+            next: 0,
+        }
+    }
+
+    pub fn push(&mut self, roll: Roll, result: i32) {
+        if N == 0 {
+            return;
+        }
+
+        self.entries[self.next] = (roll, result);
+        self.next = (self.next + 1) % N;
+        self.len = (self.len + 1).min(N);
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    // Newest first
+    pub fn iter(&self) -> impl Iterator<Item = &(Roll, i32)> {
+        (1..=self.len).map(move |back| &self.entries[(self.next + N - back) % N])
+    }
+}
+
+impl<const N: usize> Default for History<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::string::ToString;
+    use std::vec::Vec;
+
+    #[test]
+    fn test_dice() {
+        assert_eq!("d20".parse(), Ok(Roll::new(1, 20)));
+        assert_eq!("3d6+2".parse(), Ok(Roll::new(3, 6).with_modifier(2)));
+        assert_eq!(" 2D100-5 ".parse(), Ok(Roll::new(2, 100).with_modifier(-5)));
+        assert_eq!("0d6".parse::<Roll>(), Err(ParseRollError));
+        assert_eq!("6".parse::<Roll>(), Err(ParseRollError));
+        assert_eq!("d6+".parse::<Roll>(), Err(ParseRollError));
+        assert_eq!(Roll::new(3, 6).with_modifier(-1).to_string(), "3d6-1");
+
+        let roll = Roll::new(4, 6).with_modifier(1);
+        let mut seed = 1u32;
+        let mut xorshift = || {
+            seed ^= seed << 13;
+            seed ^= seed >> 17;
+            seed ^= seed << 5;
+            seed
+        };
+        for _ in 0..1000 {
+            let result = roll.roll(&mut xorshift);
+            assert!((roll.min()..=roll.max()).contains(&result));
+        }
+        assert_eq!(Roll::new(2, 6).roll(|| 5), 12);
+
+        let mut history = History::<2>::new();
+        history.push(Roll::new(1, 4), 1);
+        history.push(Roll::new(1, 6), 2);
+        history.push(Roll::new(1, 8), 3);
+        assert_eq!(history.iter().map(|&(_, result)| result).collect::<Vec<_>>(), [3, 2]);
+    }
+}
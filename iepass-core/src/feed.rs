@@ -0,0 +1,197 @@
+use embedded_io::Read;
+
+
+enum Tag {
+    Title,
+    TitleEnd,
+    CData,
+    Other,
+}
+
+// Streams <title> contents out of an RSS/Atom document without buffering the whole feed
+pub struct TitleReader<R> {
+    reader: R,
+    buf: [u8; 128],
+    pos: usize,
+    len: usize,
+}
+
+impl<R: Read> TitleReader<R> {
+    pub fn new(reader: R) -> TitleReader<R> {
+        TitleReader {
+            reader,
+            buf: [0; 128],
+            pos: 0,
+            len: 0,
+        }
+    }
+
+    fn byte(&mut self) -> Result<Option<u8>, R::Error> {
+        if self.pos >= self.len {
+            self.len = self.reader.read(&mut self.buf)?;
+            self.pos = 0;
+
+            if self.len == 0 {
+                return Ok(None);
+            }
+        }
+
+        self.pos += 1;
+        Ok(Some(self.buf[self.pos - 1]))
+    }
+
+    // Reads a tag after its '<', stopping right after the '>' (or the '[' of a CDATA section)
+    fn tag(&mut self) -> Result<Tag, R::Error> {
+        let mut name = [0; 8];
+        let mut len = 0;
+        let mut in_name = true;
+
+        while let Some(b) = self.byte()? {
+            if b == b'>' {
+                break;
+            } else if b.is_ascii_whitespace() {
+                in_name = false;
+            } else if in_name && len < name.len() {
+                name[len] = b;
+                len += 1;
+
+                if name[..len] == *b"![CDATA[" {
+                    return Ok(Tag::CData);
+                }
+            } else if in_name {
+                in_name = false;
+                len = 0;
+            }
+        }
+
+        Ok(match &name[..len] {
+            b"title" => Tag::Title,
+            b"/title" => Tag::TitleEnd,
+            _ => Tag::Other,
+        })
+    }
+
+    fn entity(&mut self) -> Result<Option<char>, R::Error> {
+        let mut name = [0; 8];
+        let mut len = 0;
+
+        while let Some(b) = self.byte()? {
+            if b == b';' {
+                break;
+            } else if len < name.len() {
+                name[len] = b;
+                len += 1;
+            }
+        }
+
+        let name = core::str::from_utf8(&name[..len]).unwrap_or_default();
+        Ok(match name {
+            "amp" => Some('&'),
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            _ => {
+                let code = if let Some(hex) = name.strip_prefix("#x").or(name.strip_prefix("#X")) {
+                    u32::from_str_radix(hex, 16).ok()
+                } else {
+                    name.strip_prefix('#').and_then(|dec| dec.parse().ok())
+                };
+
+                code.and_then(char::from_u32)
+            }
+        })
+    }
+
+    // Returns the next title, truncated to fit `out`, or None at the end of the feed
+    pub fn next_title<'b>(&mut self, out: &'b mut [u8]) -> Result<Option<&'b str>, R::Error> {
+        loop {
+            match self.byte()? {
+                None => return Ok(None),
+                Some(b'<') => {
+                    if let Tag::Title = self.tag()? {
+                        break;
+                    }
+                }
+                Some(_) => {}
+            }
+        }
+
+        let mut len = 0;
+        let mut push = |bytes: &[u8]| {
+            for &b in bytes {
+                if len < out.len() && !(len == 0 && b.is_ascii_whitespace()) {
+                    out[len] = b;
+                    len += 1;
+                }
+            }
+        };
+
+        while let Some(b) = self.byte()? {
+            match b {
+                b'<' => match self.tag()? {
+                    Tag::TitleEnd => break,
+                    Tag::CData => {
+                        let mut closing = 0;
+
+                        while let Some(b) = self.byte()? {
+                            match (closing, b) {
+                                (2, b'>') => break,
+                                (2, b']') => push(b"]"),
+                                (_, b']') => closing += 1,
+                                _ => {
+                                    push(&b"]]"[..closing]);
+                                    push(&[b]);
+                                    closing = 0;
+                                }
+                            }
+                        }
+                    }
+                    _ => {}
+                },
+                b'&' => {
+                    if let Some(c) = self.entity()? {
+                        push(c.encode_utf8(&mut [0; 4]).as_bytes());
+                    }
+                }
+                b => push(&[b]),
+            }
+        }
+
+        let valid = match core::str::from_utf8(&out[..len]) {
+            Ok(_) => len,
+            Err(err) => err.valid_up_to(),
+        };
+        let title = core::str::from_utf8(&out[..valid]).unwrap_or_default();
+
+        Ok(Some(title.trim_end()))
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FEED: &str = r#"<?xml version="1.0"?>
+<rss version="2.0"><channel>
+    <title>Hacker News</title>
+    <item><title><![CDATA[Show HN: A [badge] that <plays> Bad Apple]]></title></item>
+    <item><title type="text">
+        Tom &amp; Jerry &#8211; &#x263A;
+    </title><description>ignored</description></item>
+    <item><title>Zażółć gęślą jaźń</title></item>
+</channel></rss>"#;
+
+    #[test]
+    fn test_titles() {
+        let mut reader = TitleReader::new(FEED.as_bytes());
+        let mut buf = [0; 64];
+
+        assert_eq!(reader.next_title(&mut buf).unwrap(), Some("Hacker News"));
+        assert_eq!(reader.next_title(&mut buf).unwrap(), Some("Show HN: A [badge] that <plays> Bad Apple"));
+        assert_eq!(reader.next_title(&mut buf).unwrap(), Some("Tom & Jerry – ☺"));
+        assert_eq!(reader.next_title(&mut [0; 5]).unwrap(), Some("Zaż"));
+        assert_eq!(reader.next_title(&mut buf).unwrap(), None);
+    }
+}
@@ -0,0 +1,351 @@
+use embedded_io::Read;
+use crate::tz;
+
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DateTime {
+    pub year: i32,
+    pub month: u8,
+    pub day: u8,
+    pub hour: u8,
+    pub minute: u8,
+    pub second: u8,
+    // "Z" suffix, otherwise floating or TZID local time
+    pub utc: bool,
+    pub all_day: bool,
+}
+
+impl DateTime {
+    // Accepts "YYYYMMDD" and "YYYYMMDDTHHMMSS[Z]"
+    pub fn parse(value: &str) -> Option<DateTime> {
+        let num = |range: core::ops::Range<usize>| value.get(range)?.parse::<u16>().ok();
+        let (date, time) = match value.split_once('T') {
+            Some((date, time)) => (date, Some(time)),
+            None => (value, None),
+        };
+
+        if date.len() != 8 || !date.bytes().all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+
+        let mut datetime = DateTime {
+            year: num(0..4)? as i32,
+            month: num(4..6)? as u8,
+            day: num(6..8)? as u8,
+            hour: 0,
+            minute: 0,
+            second: 0,
+            utc: false,
+            all_day: time.is_none(),
+        };
+
+        if let Some(time) = time {
+            let (time, utc) = match time.strip_suffix('Z') {
+                Some(time) => (time, true),
+                None => (time, false),
+            };
+            if time.len() != 6 || !time.bytes().all(|b| b.is_ascii_digit()) {
+                return None;
+            }
+
+            datetime.hour = num(9..11)? as u8;
+            datetime.minute = num(11..13)? as u8;
+            datetime.second = num(13..15)? as u8;
+            datetime.utc = utc;
+        }
+
+        let valid = (1..=12).contains(&datetime.month) && (1..=31).contains(&datetime.day)
+            && datetime.hour < 24 && datetime.minute < 60 && datetime.second < 61;
+
+        valid.then_some(datetime)
+    }
+
+    // Seconds since epoch, in UTC if `utc` is set and in whatever local time was used otherwise
+    pub fn timestamp(&self) -> i64 {
+        tz::days_from_civil(self.year as i64, self.month, self.day) * 86400
+            + self.hour as i64 * 3600
+            + self.minute as i64 * 60
+            + self.second as i64
+    }
+}
+
+pub struct Property<'b> {
+    pub name: &'b str,
+    // Raw parameters without the leading ';', eg. "TZID=Europe/Warsaw;VALUE=DATE-TIME"
+    pub params: &'b str,
+    pub value: &'b str,
+}
+
+impl Property<'_> {
+    pub fn param(&self, name: &str) -> Option<&str> {
+        self.params
+            .split(';')
+            .filter_map(|param| param.split_once('='))
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.trim_matches('"'))
+    }
+}
+
+const TEXT_LEN: usize = 64;
+
+pub struct Event {
+    pub start: Option<DateTime>,
+    pub end: Option<DateTime>,
+    summary: [u8; TEXT_LEN],
+    summary_len: usize,
+    location: [u8; TEXT_LEN],
+    location_len: usize,
+}
+
+impl Event {
+    pub fn new() -> Event {
+        Event {
+            start: None,
+            end: None,
+            summary: [0; TEXT_LEN],
+            summary_len: 0,
+            location: [0; TEXT_LEN],
+            location_len: 0,
+        }
+    }
+
+    pub fn summary(&self) -> &str {
+        core::str::from_utf8(&self.summary[..self.summary_len]).unwrap_or_default()
+    }
+
+    pub fn location(&self) -> &str {
+        core::str::from_utf8(&self.location[..self.location_len]).unwrap_or_default()
+    }
+}
+
+impl Default for Event {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Unescapes a TEXT value into `out`, truncating on a char boundary
+fn unescape(value: &str, out: &mut [u8]) -> usize {
+    let mut len = 0;
+    let mut chars = value.chars();
+
+    while let Some(c) = chars.next() {
+        let c = match c {
+            '\\' => match chars.next() {
+                Some('n' | 'N') => ' ',
+                Some(c) => c,
+                None => break,
+            },
+            c => c,
+        };
+
+        if len + c.len_utf8() > out.len() {
+            break;
+        }
+        len += c.encode_utf8(&mut out[len..]).len();
+    }
+
+    len
+}
+
+// Tolerant iCalendar reader: unfolds continuation lines, truncates overlong ones and skips anything malformed
+pub struct Reader<R> {
+    reader: R,
+    buf: [u8; 64],
+    pos: usize,
+    len: usize,
+    peeked: Option<u8>,
+}
+
+impl<R: Read> Reader<R> {
+    pub fn new(reader: R) -> Reader<R> {
+        Reader {
+            reader,
+            buf: [0; 64],
+            pos: 0,
+            len: 0,
+            peeked: None,
+        }
+    }
+
+    fn byte(&mut self) -> Result<Option<u8>, R::Error> {
+        if let Some(byte) = self.peeked.take() {
+            return Ok(Some(byte));
+        }
+
+        if self.pos >= self.len {
+            self.len = self.reader.read(&mut self.buf)?;
+            self.pos = 0;
+
+            if self.len == 0 {
+                return Ok(None);
+            }
+        }
+
+        self.pos += 1;
+        Ok(Some(self.buf[self.pos - 1]))
+    }
+
+    fn line<'b>(&mut self, line: &'b mut [u8]) -> Result<Option<&'b str>, R::Error> {
+        let mut len = 0;
+
+        loop {
+            match self.byte()? {
+                None if len == 0 => return Ok(None),
+                None => break,
+                Some(b'\r') => {}
+                Some(b'\n') => match self.byte()? {
+                    Some(b' ' | b'\t') => {}
+                    next => {
+                        self.peeked = next;
+                        break;
+                    }
+                },
+                Some(byte) => {
+                    if len < line.len() {
+                        line[len] = byte;
+                        len += 1;
+                    }
+                }
+            }
+        }
+
+        let valid = match core::str::from_utf8(&line[..len]) {
+            Ok(_) => len,
+            Err(err) => err.valid_up_to(),
+        };
+
+        Ok(Some(core::str::from_utf8(&line[..valid]).unwrap_or_default()))
+    }
+
+    pub fn next_property<'b>(&mut self, line: &'b mut [u8]) -> Result<Option<Property<'b>>, R::Error> {
+        // Reborrowing `line` in a loop upsets the borrow checker, so find the length first and split after
+        let mut len = 0;
+        while len == 0 {
+            match self.line(line)? {
+                None => return Ok(None),
+                Some(text) if text.contains(':') => len = text.len(),
+                Some(_) => {}
+            }
+        }
+
+        let text = core::str::from_utf8(&line[..len]).unwrap_or_default();
+        let mut quoted = false;
+        let colon = text
+            .char_indices()
+            .find(|&(_, c)| {
+                quoted ^= c == '"';
+                c == ':' && !quoted
+            })
+            .map_or(text.len(), |(pos, _)| pos);
+
+        let (head, value) = (&text[..colon], text.get(colon + 1..).unwrap_or_default());
+        let (name, params) = head.split_once(';').unwrap_or((head, ""));
+
+        Ok(Some(Property { name, params, value }))
+    }
+
+    // Fills `event` with the next VEVENT, returns false at the end of the calendar
+    pub fn next_event(&mut self, event: &mut Event) -> Result<bool, R::Error> {
+        let mut line = [0; 256];
+        let mut in_event = false;
+        let mut nested = 0;
+
+        while let Some(property) = self.next_property(&mut line)? {
+            let is = |name: &str| property.name.eq_ignore_ascii_case(name);
+
+            if !in_event {
+                if is("BEGIN") && property.value.eq_ignore_ascii_case("VEVENT") {
+                    *event = Event::new();
+                    in_event = true;
+                }
+            } else if is("BEGIN") {
+                nested += 1;
+            } else if is("END") && nested > 0 {
+                nested -= 1;
+            } else if is("END") {
+                return Ok(true);
+            } else if nested > 0 {
+                // Ignore VALARM and friends, they have their own SUMMARY/DESCRIPTION
+            } else if is("DTSTART") {
+                event.start = DateTime::parse(property.value);
+            } else if is("DTEND") {
+                event.end = DateTime::parse(property.value);
+            } else if is("SUMMARY") {
+                event.summary_len = unescape(property.value, &mut event.summary);
+            } else if is("LOCATION") {
+                event.location_len = unescape(property.value, &mut event.location);
+            }
+        }
+
+        // Tolerate a missing END:VEVENT at the end of a truncated download
+        Ok(in_event)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CALENDAR: &str = r#"BEGIN:VCALENDAR
+VERSION:2.0
+BEGIN:VEVENT
+DTSTART;TZID="Europe/Warsaw":20251016T090000
+DTEND;TZID=Europe/Warsaw:20251016T100000
+SUMMARY:Stand-up\, then code
+  review
+LOCATION:Room 1\; floor 2
+BEGIN:VALARM
+TRIGGER:-PT15M
+SUMMARY:Alarm summary
+END:VALARM
+END:VEVENT
+this line is garbage
+BEGIN:VEVENT
+DTSTART;VALUE=DATE:20251224
+SUMMARY:Christmas Eve with a very long title that does not fit in the event buffer at all
+END:VEVENT
+BEGIN:VEVENT
+DTSTART:20251231T230000Z
+DTEND:bogus
+SUMMARY:Truncated download
+"#;
+
+    #[test]
+    fn test_events() {
+        let mut reader = Reader::new(CALENDAR.as_bytes());
+        let mut event = Event::new();
+
+        assert!(reader.next_event(&mut event).unwrap());
+        assert_eq!(event.summary(), "Stand-up, then code review");
+        assert_eq!(event.location(), "Room 1; floor 2");
+        let start = event.start.unwrap();
+        assert_eq!((start.year, start.month, start.day, start.hour, start.utc), (2025, 10, 16, 9, false));
+        assert_eq!(event.end.unwrap().timestamp() - start.timestamp(), 3600);
+
+        assert!(reader.next_event(&mut event).unwrap());
+        assert_eq!(event.summary(), "Christmas Eve with a very long title that does not fit in the ev");
+        assert!(event.start.unwrap().all_day);
+        assert_eq!(event.end, None);
+
+        assert!(reader.next_event(&mut event).unwrap());
+        assert_eq!(event.summary(), "Truncated download");
+        assert_eq!(event.start.unwrap().timestamp(), tz::days_from_civil(2025, 12, 31) * 86400 + 23 * 3600);
+        assert_eq!(event.end, None);
+
+        assert!(!reader.next_event(&mut event).unwrap());
+    }
+
+    #[test]
+    fn test_property() {
+        let mut reader = Reader::new("ATTENDEE;CN=\"Doe: John\";ROLE=CHAIR:mailto:john@example.com\n".as_bytes());
+        let mut line = [0; 128];
+        let property = reader.next_property(&mut line).unwrap().unwrap();
+
+        assert_eq!(property.name, "ATTENDEE");
+        assert_eq!(property.param("cn"), Some("Doe: John"));
+        assert_eq!(property.param("ROLE"), Some("CHAIR"));
+        assert_eq!(property.value, "mailto:john@example.com");
+    }
+}
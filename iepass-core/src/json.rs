@@ -0,0 +1,266 @@
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Token<'a> {
+    ObjectStart,
+    ObjectEnd,
+    ArrayStart,
+    ArrayEnd,
+    Key(&'a str),
+    // Escape sequences are left as-is, the slice is the raw content between the quotes
+    String(&'a str),
+    Number(&'a str),
+    Bool(bool),
+    Null,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Error {
+    UnexpectedByte(usize),
+    UnexpectedEof,
+    TooDeep,
+}
+
+impl Token<'_> {
+    pub fn as_f32(&self) -> Option<f32> {
+        match self {
+            Token::Number(number) => number.parse().ok(),
+            _ => None,
+        }
+    }
+
+    pub fn as_i32(&self) -> Option<i32> {
+        match self {
+            Token::Number(number) => number.parse().ok(),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Token::String(string) => Some(string),
+            _ => None,
+        }
+    }
+}
+
+// Pull tokenizer for a minimal JSON subset, nesting is limited to 32 levels
+pub struct Reader<'a> {
+    input: &'a str,
+    pos: usize,
+    depth: u8,
+    objects: u32,
+    expect_key: bool,
+}
+
+impl<'a> Reader<'a> {
+    pub fn new(input: &'a str) -> Reader<'a> {
+        Reader {
+            input,
+            pos: 0,
+            depth: 0,
+            objects: 0,
+            expect_key: false,
+        }
+    }
+
+    fn in_object(&self) -> bool {
+        self.depth > 0 && self.objects & (1 << (self.depth - 1)) != 0
+    }
+
+    fn push(&mut self, object: bool) -> Result<(), Error> {
+        if self.depth >= 32 {
+            return Err(Error::TooDeep);
+        }
+
+        self.objects = (self.objects & !(1 << self.depth)) | ((object as u32) << self.depth);
+        self.depth += 1;
+        self.expect_key = object;
+        Ok(())
+    }
+
+    fn pop(&mut self, object: bool) -> Result<(), Error> {
+        if self.depth == 0 || self.in_object() != object {
+            return Err(Error::UnexpectedByte(self.pos - 1));
+        }
+
+        self.depth -= 1;
+        self.expect_key = false;
+        Ok(())
+    }
+
+    fn take_while(&mut self, pred: impl Fn(u8) -> bool) -> &'a str {
+        let start = self.pos;
+        let bytes = self.input.as_bytes();
+
+        while self.pos < bytes.len() && pred(bytes[self.pos]) {
+            self.pos += 1;
+        }
+
+        &self.input[start..self.pos]
+    }
+
+    fn string(&mut self) -> Result<&'a str, Error> {
+        let start = self.pos;
+        let bytes = self.input.as_bytes();
+
+        loop {
+            match bytes.get(self.pos) {
+                None => return Err(Error::UnexpectedEof),
+                Some(b'\\') => self.pos += 2,
+                Some(b'"') => break,
+                Some(_) => self.pos += 1,
+            }
+        }
+
+        self.pos += 1;
+        Ok(&self.input[start..self.pos - 1])
+    }
+
+    fn literal(&mut self, literal: &str, token: Token<'a>) -> Result<Token<'a>, Error> {
+        let start = self.pos - 1;
+
+        if self.input[start..].starts_with(literal) {
+            self.pos = start + literal.len();
+            Ok(token)
+        } else {
+            Err(Error::UnexpectedByte(start))
+        }
+    }
+
+    pub fn next_token(&mut self) -> Result<Option<Token<'a>>, Error> {
+        loop {
+            self.take_while(|b| b.is_ascii_whitespace());
+
+            let Some(&byte) = self.input.as_bytes().get(self.pos) else {
+                return if self.depth == 0 { Ok(None) } else { Err(Error::UnexpectedEof) };
+            };
+            self.pos += 1;
+
+            return match byte {
+                b',' => {
+                    self.expect_key = self.in_object();
+                    continue;
+                }
+                b':' => {
+                    self.expect_key = false;
+                    continue;
+                }
+                b'{' => self.push(true).map(|_| Some(Token::ObjectStart)),
+                b'[' => self.push(false).map(|_| Some(Token::ArrayStart)),
+                b'}' => self.pop(true).map(|_| Some(Token::ObjectEnd)),
+                b']' => self.pop(false).map(|_| Some(Token::ArrayEnd)),
+                b'"' if self.expect_key => self.string().map(|key| Some(Token::Key(key))),
+                b'"' => self.string().map(|string| Some(Token::String(string))),
+                b't' => self.literal("true", Token::Bool(true)).map(Some),
+                b'f' => self.literal("false", Token::Bool(false)).map(Some),
+                b'n' => self.literal("null", Token::Null).map(Some),
+                b'-' | b'0'..=b'9' => {
+                    self.pos -= 1;
+                    let number = self.take_while(|b| matches!(b, b'0'..=b'9' | b'-' | b'+' | b'.' | b'e' | b'E'));
+                    Ok(Some(Token::Number(number)))
+                }
+                _ => Err(Error::UnexpectedByte(self.pos - 1)),
+            };
+        }
+    }
+
+    // Skips the value starting with the next token, including all nested values
+    pub fn skip_value(&mut self) -> Result<(), Error> {
+        match self.next_token()?.ok_or(Error::UnexpectedEof)? {
+            Token::ObjectStart | Token::ArrayStart => self.skip_container(),
+            Token::Key(_) => self.skip_value(),
+            Token::ObjectEnd | Token::ArrayEnd => Err(Error::UnexpectedByte(self.pos - 1)),
+            _ => Ok(()),
+        }
+    }
+
+    fn skip_container(&mut self) -> Result<(), Error> {
+        let mut depth = 1usize;
+
+        while depth > 0 {
+            match self.next_token()?.ok_or(Error::UnexpectedEof)? {
+                Token::ObjectStart | Token::ArrayStart => depth += 1,
+                Token::ObjectEnd | Token::ArrayEnd => depth -= 1,
+                _ => {}
+            }
+        }
+
+        Ok(())
+    }
+}
+
+// Looks up a scalar by path, numeric segments index into arrays, eg. ["weather", "0", "main"]
+pub fn find<'a>(input: &'a str, path: &[&str]) -> Result<Option<Token<'a>>, Error> {
+    let mut reader = Reader::new(input);
+
+    for segment in path {
+        match reader.next_token()? {
+            Some(Token::ObjectStart) => loop {
+                match reader.next_token()? {
+                    Some(Token::Key(key)) if key == *segment => break,
+                    Some(Token::Key(_)) => reader.skip_value()?,
+                    _ => return Ok(None),
+                }
+            },
+            Some(Token::ArrayStart) => {
+                let Ok(index) = segment.parse::<usize>() else { return Ok(None) };
+
+                for _ in 0..index {
+                    match reader.next_token()? {
+                        Some(Token::ObjectStart | Token::ArrayStart) => reader.skip_container()?,
+                        Some(Token::ArrayEnd) | None => return Ok(None),
+                        _ => {}
+                    }
+                }
+            }
+            _ => return Ok(None),
+        }
+    }
+
+    match reader.next_token()? {
+        Some(Token::ObjectEnd | Token::ArrayEnd) => Ok(None),
+        token => Ok(token),
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::vec::Vec;
+
+    const WEATHER: &str = r#"{
+        "name": "Krak\"ow",
+        "weather": [{ "id": 800, "main": "Clear" }, { "id": 701, "main": "Mist" }],
+        "main": { "temp": -3.5e0, "humidity": 93, "feels_like": null },
+        "visible": true
+    }"#;
+
+    #[test]
+    fn test_json() {
+        let mut reader = Reader::new(r#"{"a": [1, "x", {}], "b": false}"#);
+        let mut tokens = Vec::new();
+        while let Some(token) = reader.next_token().unwrap() {
+            tokens.push(token);
+        }
+        assert_eq!(tokens, [
+            Token::ObjectStart, Token::Key("a"), Token::ArrayStart, Token::Number("1"), Token::String("x"),
+            Token::ObjectStart, Token::ObjectEnd, Token::ArrayEnd, Token::Key("b"), Token::Bool(false),
+            Token::ObjectEnd,
+        ]);
+        assert_eq!(reader.next_token(), Ok(None));
+
+        assert_eq!(find(WEATHER, &["name"]), Ok(Some(Token::String(r#"Krak\"ow"#))));
+        assert_eq!(find(WEATHER, &["weather", "1", "main"]), Ok(Some(Token::String("Mist"))));
+        assert_eq!(find(WEATHER, &["main", "temp"]).unwrap().and_then(|t| t.as_f32()), Some(-3.5));
+        assert_eq!(find(WEATHER, &["main", "humidity"]).unwrap().and_then(|t| t.as_i32()), Some(93));
+        assert_eq!(find(WEATHER, &["main", "feels_like"]), Ok(Some(Token::Null)));
+        assert_eq!(find(WEATHER, &["visible"]), Ok(Some(Token::Bool(true))));
+        assert_eq!(find(WEATHER, &["main", "pressure"]), Ok(None));
+        assert_eq!(find(WEATHER, &["weather", "2"]), Ok(None));
+        assert_eq!(find(WEATHER, &["weather", "5", "id"]), Ok(None));
+
+        assert_eq!(find(r#"{"a": [1, 2"#, &["b"]), Err(Error::UnexpectedEof));
+        assert_eq!(find(r#"{"a": tru}"#, &["a"]), Err(Error::UnexpectedByte(6)));
+        assert_eq!(Reader::new("[}").skip_value(), Err(Error::UnexpectedByte(1)));
+    }
+}
@@ -1,3 +1,26 @@
 #![cfg_attr(all(not(test), not(feature = "std")), no_std)]
 
+pub mod binlog;
+pub mod blend;
+pub mod contact;
+pub mod crc32;
+pub mod dice;
+pub mod feed;
+pub mod ics;
+pub mod json;
+pub mod metrics;
+pub mod morse;
+pub mod pacing;
+pub mod packed;
+pub mod pedometer;
+pub mod pomodoro;
+pub mod progress;
+pub mod quantize;
 pub mod rle;
+pub mod rle16;
+pub mod roi;
+pub mod schedule;
+pub mod smol;
+pub mod stopwatch;
+pub mod testvectors;
+pub mod tz;
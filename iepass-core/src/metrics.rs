@@ -0,0 +1,72 @@
+use core::sync::atomic::{AtomicU32, Ordering};
+
+
+// Monotonic counter, meant to live in a static and be bumped from anywhere
+pub struct Counter {
+    pub name: &'static str,
+    value: AtomicU32,
+}
+
+impl Counter {
+    pub const fn new(name: &'static str) -> Counter {
+        Counter {
+            name,
+            value: AtomicU32::new(0),
+        }
+    }
+
+    pub fn increment(&self) {
+        self.add(1);
+    }
+
+    pub fn add(&self, n: u32) {
+        self.value.fetch_add(n, Ordering::Relaxed);
+    }
+
+    pub fn get(&self) -> u32 {
+        self.value.load(Ordering::Relaxed)
+    }
+}
+
+// Last-value metric, eg. free heap or battery voltage
+pub struct Gauge {
+    pub name: &'static str,
+    value: AtomicU32,
+}
+
+impl Gauge {
+    pub const fn new(name: &'static str) -> Gauge {
+        Gauge {
+            name,
+            value: AtomicU32::new(0),
+        }
+    }
+
+    pub fn set(&self, value: u32) {
+        self.value.store(value, Ordering::Relaxed);
+    }
+
+    pub fn get(&self) -> u32 {
+        self.value.load(Ordering::Relaxed)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    static FRAMES: Counter = Counter::new("frames");
+    static HEAP: Gauge = Gauge::new("heap");
+
+    #[test]
+    fn test_metrics() {
+        FRAMES.increment();
+        FRAMES.add(41);
+        assert_eq!((FRAMES.name, FRAMES.get()), ("frames", 42));
+
+        HEAP.set(1000);
+        HEAP.set(900);
+        assert_eq!((HEAP.name, HEAP.get()), ("heap", 900));
+    }
+}
@@ -0,0 +1,143 @@
+use core::time::Duration;
+
+
+const LETTERS: [&str; 26] = [
+    ".-", "-...", "-.-.", "-..", ".", "..-.", "--.", "....", "..", ".---", "-.-", ".-..", "--",
+    "-.", "---", ".--.", "--.-", ".-.", "...", "-", "..-", "...-", ".--", "-..-", "-.--", "--..",
+];
+
+const DIGITS: [&str; 10] = [
+    "-----", ".----", "..---", "...--", "....-", ".....", "-....", "--...", "---..", "----.",
+];
+
+pub fn encode(c: char) -> Option<&'static str> {
+    match c.to_ascii_uppercase() {
+        c @ 'A'..='Z' => Some(LETTERS[c as usize - 'A' as usize]),
+        c @ '0'..='9' => Some(DIGITS[c as usize - '0' as usize]),
+        _ => None,
+    }
+}
+
+pub fn decode(code: &str) -> Option<char> {
+    if let Some(pos) = LETTERS.iter().position(|&l| l == code) {
+        Some((b'A' + pos as u8) as char)
+    } else {
+        DIGITS.iter().position(|&d| d == code).map(|pos| (b'0' + pos as u8) as char)
+    }
+}
+
+// Yields (on, units) pairs for a message using standard timing: dot 1, dash 3, gaps 1/3/7 units
+pub struct Beacon<'a> {
+    chars: core::str::Chars<'a>,
+    code: &'static [u8],
+    pending_gap: u8,
+}
+
+impl<'a> Beacon<'a> {
+    pub fn new(message: &'a str) -> Beacon<'a> {
+        Beacon {
+            chars: message.chars(),
+            code: &[],
+            pending_gap: 0,
+        }
+    }
+}
+
+impl Iterator for Beacon<'_> {
+    type Item = (bool, u8);
+
+    fn next(&mut self) -> Option<(bool, u8)> {
+        while self.code.is_empty() {
+            let c = self.chars.next()?;
+
+            if c.is_whitespace() {
+                if self.pending_gap != 0 {
+                    self.pending_gap = 7;
+                }
+            } else if let Some(code) = encode(c) {
+                self.code = code.as_bytes();
+                if self.pending_gap != 0 {
+                    return Some((false, core::mem::replace(&mut self.pending_gap, 0)));
+                }
+            }
+        }
+
+        if self.pending_gap != 0 {
+            return Some((false, core::mem::replace(&mut self.pending_gap, 0)));
+        }
+
+        let (&symbol, rest) = self.code.split_first()?;
+        self.code = rest;
+        self.pending_gap = if rest.is_empty() { 3 } else { 1 };
+
+        Some((true, if symbol == b'-' { 3 } else { 1 }))
+    }
+}
+
+// Decodes keyed input: feed it press and release lengths, it emits characters after a letter gap
+pub struct Keyer {
+    unit: Duration,
+    code: [u8; 6],
+    len: usize,
+}
+
+impl Keyer {
+    pub fn new(unit: Duration) -> Keyer {
+        Keyer {
+            unit,
+            code: [0; 6],
+            len: 0,
+        }
+    }
+
+    pub fn press(&mut self, length: Duration) {
+        if self.len < self.code.len() {
+            self.code[self.len] = if length >= self.unit * 2 { b'-' } else { b'.' };
+            self.len += 1;
+        }
+    }
+
+    // Returns Some(None) for an unrecognized sequence so the trainer can count it as a miss
+    pub fn release(&mut self, length: Duration) -> Option<Option<char>> {
+        if self.len == 0 || length < self.unit * 2 {
+            return None;
+        }
+
+        let code = core::str::from_utf8(&self.code[..self.len]).ok();
+        self.len = 0;
+
+        Some(code.and_then(decode))
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::vec::Vec;
+
+    #[test]
+    fn test_morse() {
+        assert_eq!(encode('s'), Some("..."));
+        assert_eq!(encode('0'), Some("-----"));
+        assert_eq!(encode('!'), None);
+        assert_eq!(decode("--.-"), Some('Q'));
+        assert_eq!(decode("......"), None);
+
+        let signals: Vec<_> = Beacon::new("ET E").collect();
+        assert_eq!(signals, [(true, 1), (false, 3), (true, 3), (false, 7), (true, 1)]);
+
+        let unit = Duration::from_millis(100);
+        let mut keyer = Keyer::new(unit);
+        keyer.press(unit);
+        assert_eq!(keyer.release(unit), None);
+        keyer.press(unit * 3);
+        assert_eq!(keyer.release(unit * 3), Some(Some('A')));
+        assert_eq!(keyer.release(unit * 7), None);
+        for _ in 0..6 {
+            keyer.press(unit);
+            keyer.release(unit);
+        }
+        assert_eq!(keyer.release(unit * 3), Some(None));
+    }
+}
@@ -0,0 +1,188 @@
+use core::time::Duration;
+
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FrameStats {
+    pub frames: u32,
+    pub dropped: u32,
+    pub busy: Duration,
+}
+
+impl FrameStats {
+    pub fn average_busy(&self) -> Duration {
+        self.busy / self.frames.max(1)
+    }
+}
+
+// Paces a render loop to a target FPS: call `frame_start` before rendering and sleep for whatever `frame_done` returns
+pub struct FrameGovernor {
+    frame_time: Duration,
+    deadline: Option<Duration>,
+    started: Duration,
+    stats: FrameStats,
+}
+
+impl FrameGovernor {
+    pub fn new(fps: u32) -> FrameGovernor {
+        FrameGovernor {
+            frame_time: Duration::from_secs(1) / fps.max(1),
+            deadline: None,
+            started: Duration::ZERO,
+            stats: FrameStats::default(),
+        }
+    }
+
+    pub fn frame_time(&self) -> Duration {
+        self.frame_time
+    }
+
+    pub fn stats(&self) -> FrameStats {
+        self.stats
+    }
+
+    pub fn frame_start(&mut self, now: Duration) {
+        self.started = now;
+
+        if self.deadline.is_none() {
+            self.deadline = Some(now + self.frame_time);
+        }
+    }
+
+    // Returns how long to sleep until the next frame, a late frame resyncs instead of bursting to catch up
+    pub fn frame_done(&mut self, now: Duration) -> Duration {
+        let deadline = self.deadline.unwrap_or(now);
+
+        self.stats.frames += 1;
+        self.stats.busy += now.saturating_sub(self.started);
+
+        if now > deadline {
+            self.stats.dropped += 1;
+            self.deadline = Some(now + self.frame_time);
+            Duration::ZERO
+        } else {
+            self.deadline = Some(deadline + self.frame_time);
+            deadline - now
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Phase {
+    Input,
+    Update,
+    Draw,
+    Flush,
+}
+
+impl Phase {
+    pub const ALL: [Phase; 4] = [Phase::Input, Phase::Update, Phase::Draw, Phase::Flush];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Phase::Input => "input",
+            Phase::Update => "update",
+            Phase::Draw => "draw",
+            Phase::Flush => "flush",
+        }
+    }
+}
+
+// Splits the time a scene spends on each frame into phases and counts frames that go over its budget
+pub struct PhaseTimer {
+    pub scene: &'static str,
+    budget: Duration,
+    frame: [Duration; 4],
+    total: [Duration; 4],
+    frames: u32,
+    overruns: u32,
+}
+
+impl PhaseTimer {
+    pub fn new(scene: &'static str, budget: Duration) -> PhaseTimer {
+        PhaseTimer {
+            scene,
+            budget,
+            frame: [Duration::ZERO; 4],
+            total: [Duration::ZERO; 4],
+            frames: 0,
+            overruns: 0,
+        }
+    }
+
+    // Adds to the current frame, a phase can be recorded more than once
+    pub fn record(&mut self, phase: Phase, time: Duration) {
+        self.frame[phase as usize] += time;
+    }
+
+    // Closes the current frame, returns its phases if they added up to more than the budget
+    pub fn end_frame(&mut self) -> Option<[Duration; 4]> {
+        let frame = core::mem::take(&mut self.frame);
+        for (total, time) in self.total.iter_mut().zip(frame) {
+            *total += time;
+        }
+        self.frames += 1;
+
+        if frame.iter().sum::<Duration>() > self.budget {
+            self.overruns += 1;
+            Some(frame)
+        } else {
+            None
+        }
+    }
+
+    pub fn average(&self, phase: Phase) -> Duration {
+        self.total[phase as usize] / self.frames.max(1)
+    }
+
+    pub fn frames(&self) -> u32 {
+        self.frames
+    }
+
+    pub fn overruns(&self) -> u32 {
+        self.overruns
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_governor() {
+        let ms = Duration::from_millis;
+        let mut governor = FrameGovernor::new(50);
+        assert_eq!(governor.frame_time(), ms(20));
+
+        governor.frame_start(ms(1000));
+        assert_eq!(governor.frame_done(ms(1005)), ms(15));
+
+        governor.frame_start(ms(1020));
+        assert_eq!(governor.frame_done(ms(1050)), ms(0));
+
+        governor.frame_start(ms(1050));
+        assert_eq!(governor.frame_done(ms(1060)), ms(10));
+
+        assert_eq!(governor.stats(), FrameStats {
+            frames: 3,
+            dropped: 1,
+            busy: ms(45),
+        });
+        assert_eq!(governor.stats().average_busy(), ms(15));
+
+        let mut timer = PhaseTimer::new("player", governor.frame_time());
+        timer.record(Phase::Input, ms(1));
+        timer.record(Phase::Draw, ms(4));
+        timer.record(Phase::Flush, ms(10));
+        assert_eq!(timer.end_frame(), None);
+
+        timer.record(Phase::Update, ms(8));
+        timer.record(Phase::Flush, ms(10));
+        timer.record(Phase::Update, ms(3));
+        assert_eq!(timer.end_frame(), Some([ms(0), ms(11), ms(0), ms(10)]));
+
+        assert_eq!((timer.frames(), timer.overruns()), (2, 1));
+        let us = Duration::from_micros;
+        assert_eq!(Phase::ALL.map(|phase| timer.average(phase)), [us(500), us(5500), ms(2), ms(10)]);
+    }
+}
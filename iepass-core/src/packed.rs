@@ -0,0 +1,209 @@
+use core::fmt;
+use embedded_io::{Read, ReadExactError, Write};
+
+
+// Indexed grayscale: a palette chunk (color count, then one gray value per color) followed by frames
+// of palette indices packed 4 or 2 to a byte, first pixel in the high bits. Up to 4 colors pack to
+// 2 bits, up to 16 to 4 bits. Meant to be fed through the RLE encoder like the plain 8-bit frames.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Palette {
+    colors: [u8; 16],
+    len: u8,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Error<E> {
+    Read(E),
+    Truncated,
+    // Palette chunk with no colors or more than 16
+    BadPalette(u8),
+}
+
+impl Palette {
+    // None if there are no colors or more than 16
+    pub fn new(colors: &[u8]) -> Option<Palette> {
+        if colors.is_empty() || colors.len() > 16 {
+            return None;
+        }
+
+        let mut palette = Palette { colors: [0; 16], len: colors.len() as u8 };
+        palette.colors[..colors.len()].copy_from_slice(colors);
+        Some(palette)
+    }
+
+    // `len` evenly spaced gray levels from black to white, clamped to 2..=16
+    pub fn even(len: u8) -> Palette {
+        let len = len.clamp(2, 16);
+        let mut palette = Palette { colors: [0; 16], len };
+        for (i, color) in palette.colors[..len as usize].iter_mut().enumerate() {
+            *color = (i * 255 / (len as usize - 1)) as u8;
+        }
+        palette
+    }
+
+    pub fn colors(&self) -> &[u8] {
+        &self.colors[..self.len as usize]
+    }
+
+    pub fn bits(&self) -> u8 {
+        if self.len <= 4 { 2 } else { 4 }
+    }
+
+    // Packed size of a frame with `pixels` pixels
+    pub fn packed_len(&self, pixels: usize) -> usize {
+        pixels.div_ceil(8 / self.bits() as usize)
+    }
+
+    // Index of the closest color
+    pub fn index(&self, value: u8) -> u8 {
+        let mut best = 0;
+        for (i, &color) in self.colors().iter().enumerate() {
+            if color.abs_diff(value) < self.colors[best].abs_diff(value) {
+                best = i;
+            }
+        }
+        best as u8
+    }
+
+    pub fn write<W: Write>(&self, writer: &mut W) -> Result<(), W::Error> {
+        writer.write_all(&[self.len])?;
+        writer.write_all(self.colors())
+    }
+
+    pub fn read<R: Read>(reader: &mut R) -> Result<Palette, Error<R::Error>> {
+        let mut len = 0;
+        reader.read_exact(core::slice::from_mut(&mut len)).map_err(map_err)?;
+
+        let mut colors = [0; 16];
+        let colors = colors.get_mut(..len as usize).filter(|colors| !colors.is_empty()).ok_or(Error::BadPalette(len))?;
+        reader.read_exact(colors).map_err(map_err)?;
+
+        Ok(Palette::new(colors).unwrap())
+    }
+}
+
+fn map_err<E>(err: ReadExactError<E>) -> Error<E> {
+    match err {
+        ReadExactError::UnexpectedEof => Error::Truncated,
+        ReadExactError::Other(err) => Error::Read(err),
+    }
+}
+
+impl<E: fmt::Debug> fmt::Display for Error<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Read(err) => write!(f, "Failed to read packed frame: {err:?}"),
+            Error::Truncated => write!(f, "Truncated packed frame"),
+            Error::BadPalette(len) => write!(f, "Palette with {len} colors"),
+        }
+    }
+}
+
+impl<E: fmt::Debug> core::error::Error for Error<E> {}
+
+// Snaps every pixel to the closest palette color and writes the packed indices
+pub fn write_frame<W: Write>(writer: &mut W, frame: &[u8], palette: &Palette) -> Result<(), W::Error> {
+    let bits = palette.bits() as usize;
+    let mut chunk = [0; 64];
+
+    for pixels in frame.chunks(chunk.len() * 8 / bits) {
+        let len = palette.packed_len(pixels.len());
+        chunk[..len].fill(0);
+        for (i, &pixel) in pixels.iter().enumerate() {
+            let shift = 8 - bits - (i * bits) % 8;
+            chunk[i * bits / 8] |= palette.index(pixel) << shift;
+        }
+        writer.write_all(&chunk[..len])?;
+    }
+
+    Ok(())
+}
+
+// Unpacks the next frame into `frame` as gray values, returns false at the end of the stream
+pub fn read_frame<R: Read>(reader: &mut R, frame: &mut [u8], palette: &Palette) -> Result<bool, Error<R::Error>> {
+    let bits = palette.bits() as usize;
+    let mask = (1 << bits) - 1;
+    let mut chunk = [0; 64];
+
+    for (n, pixels) in frame.chunks_mut(chunk.len() * 8 / bits).enumerate() {
+        let len = palette.packed_len(pixels.len());
+        let mut read = 0;
+        while read < len {
+            match reader.read(&mut chunk[read..len]).map_err(Error::Read)? {
+                0 if n == 0 && read == 0 => return Ok(false),
+                0 => return Err(Error::Truncated),
+                count => read += count,
+            }
+        }
+
+        for (i, pixel) in pixels.iter_mut().enumerate() {
+            let shift = 8 - bits - (i * bits) % 8;
+            let index = (chunk[i * bits / 8] >> shift) & mask;
+            *pixel = palette.colors[index as usize];
+        }
+    }
+
+    Ok(true)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::vec::Vec;
+    use crate::quantize::Quantizer;
+    use crate::rle;
+
+    const FRAME_LEN: usize = 128 * 160;
+
+    #[test]
+    fn test_packed() {
+        let mut video = include_bytes!("../../assets/XD.raw").to_vec();
+        // 16 levels pack two pixels to a byte. Runs of a single color are already cheap in 8 bits, so this
+        // only pays off on footage with some detail, flat two-tone clips like XD at 2 or 4 levels grow.
+        Quantizer::new(16, 0).apply(&mut video, None);
+        let palette = Palette::even(16);
+        assert_eq!(palette.colors()[..3], [0, 17, 34]);
+
+        let mut gray = rle::Encoder::new(Vec::new());
+        embedded_io::Write::write_all(&mut gray, &video).unwrap();
+        let gray = gray.finalize().unwrap();
+
+        let mut packed = Vec::new();
+        palette.write(&mut packed).unwrap();
+        let mut encoder = rle::Encoder::new(packed);
+        for frame in video.chunks_exact(FRAME_LEN) {
+            write_frame(&mut encoder, frame, &palette).unwrap();
+        }
+        let packed = encoder.finalize().unwrap();
+        assert!(packed.len() < gray.len());
+
+        let mut data = &packed[..];
+        assert_eq!(Palette::read(&mut data), Ok(palette));
+        let mut decoder = rle::Decoder::new(data);
+        let mut frame = [0; FRAME_LEN];
+        for expected in video.chunks_exact(FRAME_LEN) {
+            assert_eq!(read_frame(&mut decoder, &mut frame, &palette), Ok(true));
+            assert_eq!(&frame[..], expected);
+        }
+        assert_eq!(read_frame(&mut decoder, &mut frame, &palette), Ok(false));
+
+        // 4 bit indices, odd pixel count pads the last byte
+        let palette = Palette::new(&[0, 10, 20, 30, 40]).unwrap();
+        let mut packed = Vec::new();
+        write_frame(&mut packed, &[0, 9, 42], &palette).unwrap();
+        assert_eq!(packed, [0x01, 0x40]);
+        let mut frame = [0; 3];
+        assert_eq!(read_frame(&mut &packed[..], &mut frame, &palette), Ok(true));
+        assert_eq!(frame, [0, 10, 40]);
+
+        // 2 bit indices
+        let palette = Palette::new(&[0, 255]).unwrap();
+        let mut packed = Vec::new();
+        write_frame(&mut packed, &[0, 255, 200, 0, 255], &palette).unwrap();
+        assert_eq!(packed, [0x14, 0x40]);
+
+        assert_eq!(Palette::read(&mut &[17, 0][..]), Err(Error::BadPalette(17)));
+        assert_eq!(read_frame(&mut &packed[..1], &mut [0; 5], &palette), Err(Error::Truncated));
+    }
+}
@@ -0,0 +1,90 @@
+// Counts steps from accelerometer magnitude samples taken at a fixed rate
+pub struct StepCounter {
+    threshold: i32,
+    min_interval: u32,
+    baseline: Option<i32>,
+    above: bool,
+    since_step: u32,
+    steps: u32,
+    day: Option<u32>,
+}
+
+impl StepCounter {
+    // `threshold` is in the same unit as samples, `min_interval` in samples (debounces double counting)
+    pub fn new(threshold: i32, min_interval: u32) -> StepCounter {
+        StepCounter {
+            threshold,
+            min_interval,
+            baseline: None,
+            above: false,
+            since_step: u32::MAX,
+            steps: 0,
+            day: None,
+        }
+    }
+
+    pub fn steps(&self) -> u32 {
+        self.steps
+    }
+
+    // Resets the count when the day changes, `day` is eg. days since epoch from the RTC
+    pub fn set_day(&mut self, day: u32) {
+        if self.day.is_some_and(|current| current != day) {
+            self.steps = 0;
+        }
+
+        self.day = Some(day);
+    }
+
+    // Returns true when the sample completed a step
+    pub fn push(&mut self, sample: i32) -> bool {
+        let baseline = self.baseline.get_or_insert(sample);
+        *baseline += (sample - *baseline) / 16;
+        let baseline = *baseline;
+
+        self.since_step = self.since_step.saturating_add(1);
+
+        if sample > baseline + self.threshold {
+            self.above = true;
+        } else if self.above && sample < baseline {
+            self.above = false;
+
+            if self.since_step >= self.min_interval {
+                self.since_step = 0;
+                self.steps += 1;
+                return true;
+            }
+        }
+
+        false
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_steps() {
+        // 50 Hz samples in mg: 1 g with a 400 mg bump every 25 samples (2 steps/s) and some jitter
+        let walk = |i: u32| 1000 + if i % 25 < 5 { 400 } else { 0 } + (i % 3) as i32 * 20;
+        let mut counter = StepCounter::new(200, 12);
+
+        counter.set_day(1);
+        for i in 0..500 {
+            counter.push(walk(i + 10));
+        }
+        assert_eq!(counter.steps(), 20);
+
+        for i in 0..500 {
+            counter.push(1000 + (i % 7) * 15);
+        }
+        assert_eq!(counter.steps(), 20);
+
+        counter.set_day(1);
+        assert_eq!(counter.steps(), 20);
+        counter.set_day(2);
+        assert_eq!(counter.steps(), 0);
+    }
+}
@@ -0,0 +1,138 @@
+use core::time::Duration;
+
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Phase {
+    Work,
+    ShortBreak,
+    LongBreak,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Intervals {
+    pub work: Duration,
+    pub short_break: Duration,
+    pub long_break: Duration,
+    pub long_break_every: u32,
+}
+
+impl Default for Intervals {
+    fn default() -> Self {
+        Intervals {
+            work: Duration::from_secs(25 * 60),
+            short_break: Duration::from_secs(5 * 60),
+            long_break: Duration::from_secs(15 * 60),
+            long_break_every: 4,
+        }
+    }
+}
+
+pub struct Pomodoro {
+    intervals: Intervals,
+    phase: Phase,
+    phase_started: Duration,
+    completed: u32,
+}
+
+impl Pomodoro {
+    pub fn new(intervals: Intervals, now: Duration) -> Pomodoro {
+        Pomodoro {
+            intervals,
+            phase: Phase::Work,
+            phase_started: now,
+            completed: 0,
+        }
+    }
+
+    pub fn phase(&self) -> Phase {
+        self.phase
+    }
+
+    pub fn completed(&self) -> u32 {
+        self.completed
+    }
+
+    pub fn phase_length(&self) -> Duration {
+        match self.phase {
+            Phase::Work => self.intervals.work,
+            Phase::ShortBreak => self.intervals.short_break,
+            Phase::LongBreak => self.intervals.long_break,
+        }
+    }
+
+    pub fn remaining(&self, now: Duration) -> Duration {
+        self.phase_length().saturating_sub(now.saturating_sub(self.phase_started))
+    }
+
+    // Progress of the current phase in 0..=u16::MAX, for drawing the arc without floats
+    pub fn progress(&self, now: Duration) -> u16 {
+        let length = self.phase_length().as_millis().max(1);
+        let elapsed = now.saturating_sub(self.phase_started).as_millis().min(length);
+
+        (elapsed * u16::MAX as u128 / length) as u16
+    }
+
+    // Advances to the next phase when the current one ran out, returning the new phase so the caller can play a cue
+    pub fn update(&mut self, now: Duration) -> Option<Phase> {
+        if now.saturating_sub(self.phase_started) < self.phase_length() {
+            return None;
+        }
+
+        self.phase_started += self.phase_length();
+        self.skip_phase();
+
+        Some(self.phase)
+    }
+
+    pub fn skip(&mut self, now: Duration) -> Phase {
+        self.phase_started = now;
+        self.skip_phase();
+
+        self.phase
+    }
+
+    fn skip_phase(&mut self) {
+        self.phase = match self.phase {
+            Phase::Work => {
+                self.completed += 1;
+
+                if self.completed.is_multiple_of(self.intervals.long_break_every) {
+                    Phase::LongBreak
+                } else {
+                    Phase::ShortBreak
+                }
+            }
+            Phase::ShortBreak | Phase::LongBreak => Phase::Work,
+        };
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pomodoro() {
+        let secs = Duration::from_secs;
+        let mut pomodoro = Pomodoro::new(Intervals {
+            work: secs(10),
+            short_break: secs(2),
+            long_break: secs(5),
+            long_break_every: 2,
+        }, secs(100));
+
+        assert_eq!(pomodoro.phase(), Phase::Work);
+        assert_eq!(pomodoro.update(secs(105)), None);
+        assert_eq!(pomodoro.remaining(secs(105)), secs(5));
+        assert_eq!(pomodoro.progress(secs(105)), u16::MAX / 2);
+
+        assert_eq!(pomodoro.update(secs(110)), Some(Phase::ShortBreak));
+        assert_eq!(pomodoro.update(secs(112)), Some(Phase::Work));
+        assert_eq!(pomodoro.update(secs(122)), Some(Phase::LongBreak));
+        assert_eq!(pomodoro.completed(), 2);
+
+        assert_eq!(pomodoro.skip(secs(123)), Phase::Work);
+        assert_eq!(pomodoro.remaining(secs(123)), secs(10));
+    }
+}
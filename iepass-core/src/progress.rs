@@ -0,0 +1,124 @@
+use core::sync::atomic::{AtomicBool, AtomicU32, AtomicUsize, Ordering};
+
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cancelled;
+
+// Shared between a long running task and whatever displays it. The task reports progress and polls
+// `check` so it can bail out with `?`, the UI side reads it and can request cancellation.
+// Messages come from a fixed list of stages so they can be swapped atomically.
+pub struct Progress {
+    stages: &'static [&'static str],
+    stage: AtomicUsize,
+    done: AtomicU32,
+    total: AtomicU32,
+    cancelled: AtomicBool,
+}
+
+impl Progress {
+    pub const fn new(stages: &'static [&'static str]) -> Progress {
+        Progress {
+            stages,
+            stage: AtomicUsize::new(0),
+            done: AtomicU32::new(0),
+            total: AtomicU32::new(0),
+            cancelled: AtomicBool::new(false),
+        }
+    }
+
+    // Moves to the next stage, progress restarts from zero
+    pub fn stage(&self, stage: usize, total: u32) {
+        self.done.store(0, Ordering::Relaxed);
+        self.total.store(total, Ordering::Relaxed);
+        self.stage.store(stage.min(self.stages.len().saturating_sub(1)), Ordering::Relaxed);
+    }
+
+    pub fn advance(&self, n: u32) {
+        self.done.fetch_add(n, Ordering::Relaxed);
+    }
+
+    pub fn set(&self, done: u32) {
+        self.done.store(done, Ordering::Relaxed);
+    }
+
+    pub fn message(&self) -> &'static str {
+        self.stages.get(self.stage.load(Ordering::Relaxed)).copied().unwrap_or("")
+    }
+
+    pub fn stage_index(&self) -> usize {
+        self.stage.load(Ordering::Relaxed)
+    }
+
+    pub fn percent(&self) -> u8 {
+        let total = self.total.load(Ordering::Relaxed);
+        if total == 0 {
+            return 0;
+        }
+
+        let done = self.done.load(Ordering::Relaxed).min(total);
+        (done as u64 * 100 / total as u64) as u8
+    }
+
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+
+    pub fn check(&self) -> Result<(), Cancelled> {
+        if self.is_cancelled() { Err(Cancelled) } else { Ok(()) }
+    }
+
+    // Clears everything, so a static handle can be reused for the next operation
+    pub fn reset(&self) {
+        self.stage(0, 0);
+        self.cancelled.store(false, Ordering::Relaxed);
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    static PROGRESS: Progress = Progress::new(&["Downloading", "Verifying"]);
+
+    fn task(progress: &Progress) -> Result<u32, Cancelled> {
+        let mut sum = 0;
+
+        progress.stage(0, 10);
+        for chunk in 0..10 {
+            progress.check()?;
+            sum += chunk;
+            progress.advance(1);
+
+            if chunk == 4 {
+                assert_eq!((progress.message(), progress.percent()), ("Downloading", 50));
+            }
+        }
+
+        progress.stage(1, 3);
+        progress.set(1);
+        assert_eq!((progress.message(), progress.percent()), ("Verifying", 33));
+        progress.check()?;
+
+        Ok(sum)
+    }
+
+    #[test]
+    fn test_progress() {
+        assert_eq!((PROGRESS.message(), PROGRESS.percent()), ("Downloading", 0));
+        assert_eq!(task(&PROGRESS), Ok(45));
+
+        PROGRESS.cancel();
+        assert_eq!(task(&PROGRESS), Err(Cancelled));
+        assert_eq!(PROGRESS.percent(), 0);
+
+        PROGRESS.reset();
+        PROGRESS.stage(5, 0);
+        assert_eq!((PROGRESS.stage_index(), PROGRESS.message()), (1, "Verifying"));
+        assert!(!PROGRESS.is_cancelled());
+    }
+}
@@ -0,0 +1,84 @@
+// Lossy grayscale stage: snaps pixels to a few evenly spaced gray levels, so camera noise stops
+// breaking up RLE runs. A pixel keeps its previous level until it moves far enough past the
+// boundary, otherwise values sitting between two levels flicker from frame to frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Quantizer {
+    levels: u8,
+    hysteresis: u8,
+}
+
+impl Quantizer {
+    // `levels` is clamped to 2..=255, `hysteresis` is in gray values past the midpoint between levels
+    pub fn new(levels: u8, hysteresis: u8) -> Quantizer {
+        Quantizer {
+            levels: levels.max(2),
+            hysteresis,
+        }
+    }
+
+    fn step(&self) -> u16 {
+        255 / (self.levels as u16 - 1)
+    }
+
+    pub fn level(&self, value: u8) -> u8 {
+        let max = self.levels as u16 - 1;
+        let index = (value as u16 * max + 127) / 255;
+        (index * 255 / max) as u8
+    }
+
+    // Quantizes `frame` in place, `prev` is the previous quantized frame, if any
+    pub fn apply(&self, frame: &mut [u8], prev: Option<&[u8]>) {
+        let keep = self.step() / 2 + self.hysteresis as u16;
+
+        match prev {
+            None => frame.iter_mut().for_each(|pixel| *pixel = self.level(*pixel)),
+            Some(prev) => {
+                for (pixel, &prev) in frame.iter_mut().zip(prev) {
+                    *pixel = if (*pixel as u16).abs_diff(prev as u16) <= keep { prev } else { self.level(*pixel) };
+                }
+            }
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::vec::Vec;
+    use crate::rle;
+
+    #[test]
+    fn test_quantize() {
+        let quantizer = Quantizer::new(4, 8);
+        assert_eq!([0, 42, 43, 127, 128, 255].map(|v| quantizer.level(v)), [0, 0, 85, 85, 170, 255]);
+
+        // Noise around a level boundary settles on one level instead of flickering
+        let mut prev = [40, 130, 200];
+        quantizer.apply(&mut prev, None);
+        assert_eq!(prev, [0, 170, 170]);
+
+        let mut frame = [46, 125, 255];
+        quantizer.apply(&mut frame, Some(&prev));
+        assert_eq!(frame, [0, 170, 255]);
+
+        let mut frame = [55, 115, 255];
+        quantizer.apply(&mut frame, Some(&prev));
+        assert_eq!(frame, [85, 85, 255]);
+
+        // Fewer levels means longer runs
+        let size = |levels| {
+            let mut video = include_bytes!("../../assets/XD.raw").to_vec();
+            let mut prev: Option<Vec<u8>> = None;
+            for frame in video.chunks_exact_mut(160 * 128) {
+                Quantizer::new(levels, 4).apply(frame, prev.as_deref());
+                prev = Some(frame.to_vec());
+            }
+
+            let mut encoder = rle::Encoder::new(Vec::new());
+            embedded_io::Write::write_all(&mut encoder, &video).unwrap();
+            encoder.finalize().unwrap().len()
+        };
+        assert!(size(4) < size(16));
+    }
+}
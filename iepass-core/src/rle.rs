@@ -1,36 +1,205 @@
-use core::slice;
-use embedded_io::{ErrorType, Read, ReadExactError, Write};
+use core::{fmt, slice};
+use core::convert::Infallible;
+use embedded_io::{ErrorKind, ErrorType, Read, ReadExactError, SliceWriteError, Write};
+#[cfg(feature = "graphics")]
+use embedded_graphics_core::pixelcolor::{raw::RawU16, Rgb565};
 
 
+// How run headers are stored. Classic packs the kind and length into one byte, so repeats stop at 128
+// and literals at 129. Varint stores (len - 1) << 1 | repeat as a LEB128 varint, so a whole black
+// frame is a single run of 3-4 bytes. The stream doesn't say which one it uses, the container does.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RunFormat {
+    #[default]
+    Classic,
+    Varint,
+}
+
+impl RunFormat {
+    fn max_repeat(self) -> u32 {
+        match self {
+            RunFormat::Classic => 128,
+            RunFormat::Varint => 1 << 31,
+        }
+    }
+}
+
+const MAX_HEADER_LEN: usize = 5;
+
 #[derive(Debug)]
 enum WriteState {
-    Repeat { len: u8, byte: u8 },
+    Repeat { len: u32, byte: u8 },
     Literal { len: u8, bytes: [u8; 130] },
 }
 
+impl WriteState {
+    // Writes the run header into `buf`, returns its length
+    fn header(&self, format: RunFormat, buf: &mut [u8; MAX_HEADER_LEN]) -> usize {
+        match (format, self) {
+            (RunFormat::Classic, WriteState::Repeat { len, .. }) => buf[0] = 0x80 | (len - 1) as u8,
+            (RunFormat::Classic, WriteState::Literal { len, .. }) => buf[0] = len - 2,
+            (RunFormat::Varint, WriteState::Repeat { len, .. }) => return write_varint((len - 1) << 1 | 1, buf),
+            (RunFormat::Varint, WriteState::Literal { len, .. }) => return write_varint((*len as u32 - 1) << 1, buf),
+        }
+        1
+    }
+
+    fn payload(&self) -> &[u8] {
+        match self {
+            WriteState::Repeat { byte, .. } => slice::from_ref(byte),
+            WriteState::Literal { len, bytes } => &bytes[0..*len as usize],
+        }
+    }
+}
+
+fn write_varint(mut value: u32, buf: &mut [u8; MAX_HEADER_LEN]) -> usize {
+    let mut len = 0;
+    while value >= 0x80 {
+        buf[len] = value as u8 | 0x80;
+        value >>= 7;
+        len += 1;
+    }
+    buf[len] = value as u8;
+    len + 1
+}
+
+// What an encoder has done so far, for comparing run formats and reporting compression ratios
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Stats {
+    pub bytes_in: u64,
+    pub bytes_out: u64,
+    pub repeat_runs: u32,
+    pub literal_runs: u32,
+    pub longest_run: u32,
+}
+
+impl Stats {
+    fn record(&mut self, state: &WriteState, format: RunFormat) {
+        let len = match *state {
+            WriteState::Repeat { len, .. } => {
+                self.repeat_runs += 1;
+                len
+            }
+            WriteState::Literal { len, .. } => {
+                self.literal_runs += 1;
+                len as u32
+            }
+        };
+        self.longest_run = self.longest_run.max(len);
+        self.bytes_out += (state.header(format, &mut [0; MAX_HEADER_LEN]) + state.payload().len()) as u64;
+    }
+
+    // Encoded size over input size, 0 before anything was written
+    pub fn ratio(&self) -> f32 {
+        if self.bytes_in == 0 { 0.0 } else { self.bytes_out as f32 / self.bytes_in as f32 }
+    }
+}
+
 pub struct Encoder<W> {
     writer: W,
+    format: RunFormat,
     state: Option<WriteState>,
+    stats: Stats,
 }
 
-impl<W: Write> Encoder<W> {
+impl<W> Encoder<W> {
     pub fn new(writer: W) -> Encoder<W> {
         Encoder {
             writer,
+            format: RunFormat::Classic,
             state: None,
+            stats: Stats::default(),
+        }
+    }
+
+    pub fn with_format(mut self, format: RunFormat) -> Encoder<W> {
+        self.format = format;
+        self
+    }
+
+    // Counts the run that is still being built as if it was written out now
+    pub fn stats(&self) -> Stats {
+        let mut stats = self.stats;
+        if let Some(state) = &self.state {
+            stats.record(state, self.format);
         }
+        stats
     }
 
-    fn write_state(&mut self) -> Result<(), W::Error> {
-        match self.state.take() {
-            None => {}
-            Some(WriteState::Repeat { byte, len }) => {
-                self.writer.write_all(&[0x80 | len - 1, byte])?
+    // Feeds one byte to the state machine, returns a finished run that has to be written out
+    fn push(&mut self, new_byte: u8) -> Option<WriteState> {
+        let max_repeat = self.format.max_repeat();
+        self.stats.bytes_in += 1;
+
+        match self.state {
+            // New Byte
+            None => {
+                self.state = Some(WriteState::Repeat {
+                    len: 1,
+                    byte: new_byte,
+                });
+                None
+            }
+            // Append to Repeat
+            Some(WriteState::Repeat {
+                ref mut len,
+                byte,
+            }) if byte == new_byte && *len < max_repeat => {
+                *len += 1;
+                None
+            }
+            // Transform singleton repeat into Literal
+            Some(WriteState::Repeat { len: 1, byte }) if byte != new_byte => {
+                let mut bytes = [0; 130];
+                bytes[0] = byte;
+                bytes[1] = new_byte;
+                self.state = Some(WriteState::Literal { len: 2, bytes });
+                None
             }
-            Some(WriteState::Literal { bytes, len, .. }) => {
-                self.writer.write_all(&[len - 2])?;
-                self.writer.write_all(&bytes[0..len as usize])?;
+            // Split Literal and flush
+            Some(WriteState::Literal {
+                len: ref mut len @ 2..,
+                ref mut bytes,
+            }) if bytes[*len as usize - 1] == new_byte => {
+                if *len > 2 {
+                    *len -= 1;
+                } else {
+                    self.state = Some(WriteState::Repeat {
+                        len: 1,
+                        byte: bytes[0],
+                    });
+                }
+                self.state.replace(WriteState::Repeat {
+                    len: 2,
+                    byte: new_byte,
+                })
+            }
+            // Append to Literal
+            Some(WriteState::Literal {
+                len: ref mut len @ 0..129,
+                ref mut bytes,
+            }) => {
+                bytes[*len as usize] = new_byte;
+                *len += 1;
+                None
             }
+            // Flush and start new Repeat
+            _ => self.state.replace(WriteState::Repeat {
+                len: 1,
+                byte: new_byte,
+            }),
+        }
+    }
+}
+
+impl<W: Write> Encoder<W> {
+    fn write_state(&mut self, state: Option<WriteState>) -> Result<(), W::Error> {
+        if let Some(state) = state {
+            let mut header = [0; MAX_HEADER_LEN];
+            let len = state.header(self.format, &mut header);
+            self.writer.write_all(&header[..len])?;
+            self.writer.write_all(state.payload())?;
+            self.stats.record(&state, self.format);
         }
         Ok(())
     }
@@ -41,77 +210,22 @@ impl<W: Write> Encoder<W> {
     }
 }
 
-impl<W: Write> ErrorType for Encoder<W> {
+impl<W: ErrorType> ErrorType for Encoder<W> {
     type Error = W::Error;
 }
 
 impl<W: Write> Write for Encoder<W> {
     fn write(&mut self, buf: &[u8]) -> Result<usize, W::Error> {
         for &new_byte in buf {
-            match self.state {
-                // New Byte
-                None => {
-                    self.state = Some(WriteState::Repeat {
-                        len: 1,
-                        byte: new_byte,
-                    })
-                }
-                // Append to Repeat
-                Some(WriteState::Repeat {
-                    len: ref mut len @ ..128,
-                    byte,
-                }) if byte == new_byte => {
-                    *len += 1;
-                }
-                // Transform singleton repeat into Literal
-                Some(WriteState::Repeat { len: 1, byte }) if byte != new_byte => {
-                    let mut bytes = [0; 130];
-                    bytes[0] = byte;
-                    bytes[1] = new_byte;
-                    self.state = Some(WriteState::Literal { len: 2, bytes });
-                }
-                // Split Literal and flush
-                Some(WriteState::Literal {
-                    len: ref mut len @ 2..,
-                    ref mut bytes,
-                }) if bytes[*len as usize - 1] == new_byte => {
-                    if *len > 2 {
-                        *len -= 1;
-                    } else {
-                        self.state = Some(WriteState::Repeat {
-                            len: 1,
-                            byte: bytes[0],
-                        });
-                    }
-                    self.write_state()?;
-                    self.state = Some(WriteState::Repeat {
-                        len: 2,
-                        byte: new_byte,
-                    });
-                }
-                // Append to Literal
-                Some(WriteState::Literal {
-                    len: ref mut len @ 0..129,
-                    ref mut bytes,
-                }) => {
-                    bytes[*len as usize] = new_byte;
-                    *len += 1;
-                }
-                // Flush and start new Repeat
-                _ => {
-                    self.write_state()?;
-                    self.state = Some(WriteState::Repeat {
-                        len: 1,
-                        byte: new_byte,
-                    });
-                }
-            };
+            let state = self.push(new_byte);
+            self.write_state(state)?;
         }
         Ok(buf.len())
     }
 
     fn flush(&mut self) -> Result<(), W::Error> {
-        self.write_state()?;
+        let state = self.state.take();
+        self.write_state(state)?;
         self.writer.flush()?;
         Ok(())
     }
@@ -129,58 +243,240 @@ enum ReadState {
     },
 }
 
+// Length and kind of a run, decoded from its header
+#[derive(Debug, Clone, Copy)]
+struct Run {
+    len: usize,
+    repeat: bool,
+}
+
+impl Run {
+    // Payload size in the encoded stream
+    fn encoded_len(&self) -> usize {
+        if self.repeat { 1 } else { self.len }
+    }
+}
+
+// Collects a run header one byte at a time
+struct RunHeader {
+    format: RunFormat,
+    value: u32,
+    shift: u32,
+}
+
+impl RunHeader {
+    fn new(format: RunFormat) -> RunHeader {
+        RunHeader { format, value: 0, shift: 0 }
+    }
+
+    // Ok(None) while more bytes are needed, Err with the offending byte if the header is invalid
+    fn push(&mut self, byte: u8) -> Result<Option<Run>, u8> {
+        match self.format {
+            RunFormat::Classic if byte < 0x80 => Ok(Some(Run { len: byte as usize + 2, repeat: false })),
+            RunFormat::Classic => Ok(Some(Run { len: (byte & !0x80) as usize + 1, repeat: true })),
+            RunFormat::Varint => {
+                // The fifth byte only has room for the top 4 bits and can't continue
+                if self.shift == 28 && byte > 0x0F {
+                    return Err(byte);
+                }
+
+                self.value |= ((byte & 0x7F) as u32) << self.shift;
+                self.shift += 7;
+                if byte & 0x80 != 0 {
+                    return Ok(None);
+                }
+
+                let run = Run { len: (self.value >> 1) as usize + 1, repeat: self.value & 1 == 1 };
+                // Literals have to fit the decoder's buffer
+                if !run.repeat && run.len > 130 {
+                    return Err(byte);
+                }
+                Ok(Some(run))
+            }
+        }
+    }
+}
+
+impl ReadState {
+    // Empty state for a run, its payload has to be read into `payload_mut`
+    fn new(run: Run) -> ReadState {
+        if run.repeat {
+            ReadState::Repeat { len: run.len, byte: 0 }
+        } else {
+            ReadState::Literal { len: run.len, pos: 0, bytes: [0; 130] }
+        }
+    }
+
+    fn payload_mut(&mut self) -> &mut [u8] {
+        match self {
+            ReadState::Literal { len, bytes, .. } => &mut bytes[0..*len],
+            ReadState::Repeat { byte, .. } => slice::from_mut(byte),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DecodeError<E> {
+    Read(E),
+    // The stream ended in the middle of a run
+    TruncatedStream,
+    // Varint run header that overflows or describes a literal too long to buffer, with the byte
+    // that made it invalid. Every byte is a valid classic header.
+    InvalidHeader(u8),
+}
+
+impl<E: fmt::Debug> fmt::Display for DecodeError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DecodeError::Read(err) => write!(f, "Failed to read RLE stream: {err:?}"),
+            DecodeError::TruncatedStream => write!(f, "Truncated RLE stream"),
+            DecodeError::InvalidHeader(header) => write!(f, "Invalid RLE run header {header:#04x}"),
+        }
+    }
+}
+
+impl<E: fmt::Debug> core::error::Error for DecodeError<E> {}
+
+impl<E: embedded_io::Error> embedded_io::Error for DecodeError<E> {
+    fn kind(&self) -> ErrorKind {
+        match self {
+            DecodeError::Read(err) => err.kind(),
+            DecodeError::TruncatedStream | DecodeError::InvalidHeader(_) => ErrorKind::InvalidData,
+        }
+    }
+}
+
 pub struct Decoder<R> {
     reader: R,
+    format: RunFormat,
     state: Option<ReadState>,
+    // Decoded bytes handed out or skipped so far
+    position: u64,
 }
 
-impl<R: Read> Decoder<R> {
+impl<R> Decoder<R> {
     pub fn new(reader: R) -> Decoder<R> {
         Decoder {
             reader,
+            format: RunFormat::Classic,
             state: None,
+            position: 0,
         }
     }
 
-    fn read_state(&mut self) -> Result<(), R::Error> {
-        let mut len = 0;
-        match self.reader.read_exact(slice::from_mut(&mut len)) {
-            Ok(_) => {}
-            Err(ReadExactError::UnexpectedEof) => {
-                self.state = None;
-                return Ok(());
+    pub fn with_format(mut self, format: RunFormat) -> Decoder<R> {
+        self.format = format;
+        self
+    }
+
+    // Copies as much of the current run as fits into `buf`
+    fn serve(&mut self, buf: &mut [u8]) -> usize {
+        let to_be_written = match self.state {
+            None => 0,
+            Some(ReadState::Literal { ref bytes, len, pos }) => {
+                let to_be_written = buf.len().min(len - pos);
+                buf[0..to_be_written].copy_from_slice(&bytes[pos..(pos + to_be_written)]);
+                to_be_written
+            }
+            Some(ReadState::Repeat { byte, len }) => {
+                let to_be_written = buf.len().min(len);
+                buf[0..to_be_written].fill(byte);
+                to_be_written
+            }
+        };
+
+        self.advance(to_be_written)
+    }
+
+    // Moves up to `len` bytes into the current run, returns how far it got
+    fn advance(&mut self, len: usize) -> usize {
+        let (advanced, done) = match self.state {
+            None => return 0,
+            Some(ReadState::Literal { len: run_len, ref mut pos, .. }) => {
+                let advanced = len.min(run_len - *pos);
+                *pos += advanced;
+                (advanced, *pos == run_len)
             }
-            Err(ReadExactError::Other(err)) => return Err(err),
+            Some(ReadState::Repeat { len: ref mut run_len, .. }) => {
+                let advanced = len.min(*run_len);
+                *run_len -= advanced;
+                (advanced, *run_len == 0)
+            }
+        };
+
+        if done {
+            self.state = None;
         }
+        self.position += advanced as u64;
+        advanced
+    }
+}
 
-        if len < 0x80 {
-            let len = len as usize + 2;
-            let mut bytes = [0; 130];
-            self.reader
-                .read_exact(&mut bytes[0..len])
-                .map_err(|err| match err {
-                    ReadExactError::UnexpectedEof => panic!("Unexpected EOF"),
-                    ReadExactError::Other(err) => err,
-                })?;
-            self.state = Some(ReadState::Literal { bytes, len, pos: 0 });
-        } else {
-            let len = (len & !0x80) as usize + 1;
+impl<R: Read> Decoder<R> {
+    fn read_state(&mut self) -> Result<(), DecodeError<R::Error>> {
+        let mut header = RunHeader::new(self.format);
+        let mut first = true;
+        let run = loop {
             let mut byte = 0;
-            self.reader
-                .read_exact(slice::from_mut(&mut byte))
-                .map_err(|err| match err {
-                    ReadExactError::UnexpectedEof => panic!("Unexpected EOF"),
-                    ReadExactError::Other(err) => err,
-                })?;
-            self.state = Some(ReadState::Repeat { byte, len });
-        }
+            match self.reader.read_exact(slice::from_mut(&mut byte)) {
+                Ok(_) => {}
+                Err(ReadExactError::UnexpectedEof) if first => {
+                    self.state = None;
+                    return Ok(());
+                }
+                Err(ReadExactError::UnexpectedEof) => return Err(DecodeError::TruncatedStream),
+                Err(ReadExactError::Other(err)) => return Err(DecodeError::Read(err)),
+            }
+            first = false;
+
+            if let Some(run) = header.push(byte).map_err(DecodeError::InvalidHeader)? {
+                break run;
+            }
+        };
+
+        let mut state = ReadState::new(run);
+        self.reader
+            .read_exact(state.payload_mut())
+            .map_err(|err| match err {
+                ReadExactError::UnexpectedEof => DecodeError::TruncatedStream,
+                ReadExactError::Other(err) => DecodeError::Read(err),
+            })?;
+        self.state = Some(state);
 
         Ok(())
     }
+
+    // Throws away `len` decoded bytes, returns how many there were before the stream ended. Repeats are
+    // skipped by their length alone, literal payloads still have to be read past.
+    pub fn skip_bytes(&mut self, len: usize) -> Result<usize, DecodeError<R::Error>> {
+        let mut skipped = 0;
+
+        while skipped < len {
+            if self.state.is_none() {
+                self.read_state()?;
+            }
+            match self.advance(len - skipped) {
+                0 => break,
+                advanced => skipped += advanced,
+            }
+        }
+
+        Ok(skipped)
+    }
+
+    // Skips whole frames, returns how many there were before the stream ended
+    pub fn skip_frames(&mut self, frames: usize, frame_len: usize) -> Result<usize, DecodeError<R::Error>> {
+        let skipped = self.skip_bytes(frames * frame_len)?;
+        if frame_len == 0 || skipped % frame_len != 0 {
+            return Err(DecodeError::TruncatedStream);
+        }
+
+        Ok(skipped / frame_len)
+    }
 }
 
-impl<R: Read> ErrorType for Decoder<R> {
-    type Error = R::Error;
+impl<R: ErrorType> ErrorType for Decoder<R> {
+    type Error = DecodeError<R::Error>;
 }
 
 impl<R: Read> Read for Decoder<R> {
@@ -189,36 +485,421 @@ impl<R: Read> Read for Decoder<R> {
             self.read_state()?;
         }
 
-        match self.state {
-            None => Ok(0),
-            Some(ReadState::Literal {
-                ref bytes,
-                len,
-                ref mut pos,
-            }) => {
-                let to_be_written = buf.len().min(len - *pos);
-                buf[0..to_be_written].copy_from_slice(&bytes[*pos..(*pos + to_be_written)]);
+        Ok(self.serve(buf))
+    }
+}
 
-                if *pos + to_be_written >= len {
-                    self.state = None;
-                } else {
-                    *pos += to_be_written;
+// Fixed-buffer helpers, eg. for keeping a compressed framebuffer snapshot around without an allocator
+pub fn encode_to_slice(input: &[u8], output: &mut [u8]) -> Result<usize, SliceWriteError> {
+    let capacity = output.len();
+    let mut encoder = Encoder::new(output);
+    encoder.write_all(input)?;
+    let rest = encoder.finalize()?;
+
+    Ok(capacity - rest.len())
+}
+
+// Decodes until `output` is full or the input ends, returning the decoded length
+pub fn decode_to_slice(input: &[u8], output: &mut [u8]) -> usize {
+    let mut decoder = Decoder::new(input);
+    let mut len = 0;
+
+    while len < output.len() {
+        match decoder.read(&mut output[len..]) {
+            Ok(0) | Err(_) => break,
+            Ok(read) => len += read,
+        }
+    }
+
+    len
+}
+
+// Where a frame starts in the encoded stream: the run containing its first byte and how much of that
+// run belongs to the previous frame. Runs are not aligned to frames, so both are needed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct FramePosition {
+    pub offset: u32,
+    pub skip: u32,
+}
+
+// Records the position of every `stride`th frame, returns how many entries were filled
+pub fn index_frames(input: &[u8], format: RunFormat, frame_len: usize, stride: usize, index: &mut [FramePosition]) -> usize {
+    let step = frame_len * stride.max(1);
+    let mut offset = 0;
+    let mut decoded = 0;
+    let mut len = 0;
+
+    'runs: while offset < input.len() && len < index.len() {
+        let mut header = RunHeader::new(format);
+        let mut header_len = 0;
+        let run = loop {
+            let Some(&byte) = input.get(offset + header_len) else { break 'runs };
+            header_len += 1;
+            match header.push(byte) {
+                Ok(Some(run)) => break run,
+                Ok(None) => {}
+                Err(_) => break 'runs,
+            }
+        };
+
+        while len < index.len() && len * step < decoded + run.len {
+            index[len] = FramePosition {
+                offset: offset as u32,
+                skip: (len * step - decoded) as u32,
+            };
+            len += 1;
+        }
+
+        offset += header_len + run.encoded_len();
+        decoded += run.len;
+    }
+
+    len
+}
+
+// Decoder over an in-memory stream that can jump to any frame using an index from index_frames
+pub struct SeekableDecoder<'a, 'i> {
+    input: &'a [u8],
+    index: &'i [FramePosition],
+    frame_len: usize,
+    stride: usize,
+    format: RunFormat,
+    decoder: Decoder<&'a [u8]>,
+}
+
+impl<'a, 'i> SeekableDecoder<'a, 'i> {
+    pub fn new(input: &'a [u8], frame_len: usize, stride: usize, index: &'i [FramePosition]) -> Self {
+        SeekableDecoder {
+            input,
+            index,
+            frame_len,
+            stride: stride.max(1),
+            format: RunFormat::Classic,
+            decoder: Decoder::new(input),
+        }
+    }
+
+    // Has to match the format the index was built with
+    pub fn with_format(mut self, format: RunFormat) -> Self {
+        self.format = format;
+        self.decoder = Decoder::new(self.input).with_format(format);
+        self
+    }
+
+    fn skip(&mut self, len: usize) -> bool {
+        self.decoder.skip_bytes(len) == Ok(len)
+    }
+
+    // Positions the decoder at the start of `frame`, returns false if it is past the end of the stream
+    pub fn seek_frame(&mut self, frame: usize) -> bool {
+        let Some(position) = self.index.get(frame / self.stride).or(self.index.last()) else { return false };
+        let Some(input) = self.input.get(position.offset as usize..) else { return false };
+
+        let indexed = (frame / self.stride).min(self.index.len() - 1) * self.stride;
+        self.decoder = Decoder::new(input).with_format(self.format);
+        self.skip(position.skip as usize + (frame - indexed) * self.frame_len)
+    }
+}
+
+impl ErrorType for SeekableDecoder<'_, '_> {
+    type Error = DecodeError<Infallible>;
+}
+
+impl Read for SeekableDecoder<'_, '_> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        self.decoder.read(buf)
+    }
+}
+
+// Inter-frame mode: every `keyframe_interval`th frame is stored as is, the ones in between are XORed
+// with the previous frame first, so unchanged pixels become long zero runs. Keyframes keep seeking
+// possible, a decoder only has to go back to the last one.
+pub struct DeltaEncoder<'b, W> {
+    encoder: Encoder<W>,
+    prev: &'b mut [u8],
+    keyframe_interval: usize,
+    frame: usize,
+}
+
+impl<'b, W: Write> DeltaEncoder<'b, W> {
+    // `prev` is scratch space for the previous frame, its length is the frame length
+    pub fn new(encoder: Encoder<W>, prev: &'b mut [u8], keyframe_interval: usize) -> Self {
+        DeltaEncoder {
+            encoder,
+            prev,
+            keyframe_interval: keyframe_interval.max(1),
+            frame: 0,
+        }
+    }
+
+    pub fn write_frame(&mut self, frame: &[u8]) -> Result<(), W::Error> {
+        if self.frame.is_multiple_of(self.keyframe_interval) {
+            self.encoder.write_all(frame)?;
+        } else {
+            let mut chunk = [0; 64];
+
+            for (frame, prev) in frame.chunks(chunk.len()).zip(self.prev.chunks(chunk.len())) {
+                for ((out, new), old) in chunk.iter_mut().zip(frame).zip(prev) {
+                    *out = new ^ old;
                 }
+                self.encoder.write_all(&chunk[..frame.len()])?;
+            }
+        }
+
+        self.prev.copy_from_slice(frame);
+        self.frame += 1;
+        Ok(())
+    }
+
+    pub fn stats(&self) -> Stats {
+        self.encoder.stats()
+    }
+
+    // Writes out the pending run, so the next frame starts a new one
+    pub fn flush(&mut self) -> Result<(), W::Error> {
+        self.encoder.flush()
+    }
+
+    pub fn finalize(self) -> Result<W, W::Error> {
+        self.encoder.finalize()
+    }
+}
+
+// Reads frames written by DeltaEncoder from an RLE decoder, eg. Decoder or SeekableDecoder
+// Fills `buf`, returns false if the stream ended right where it starts. Ending partway through is an error.
+fn read_frame_start<R: Read>(reader: &mut R, buf: &mut [u8]) -> Result<bool, ReadExactError<R::Error>> {
+    let read = reader.read(buf).map_err(ReadExactError::Other)?;
+    if read == 0 && !buf.is_empty() {
+        return Ok(false);
+    }
 
-                Ok(to_be_written)
+    reader.read_exact(&mut buf[read..])?;
+    Ok(true)
+}
+
+pub struct DeltaDecoder<'b, R> {
+    decoder: R,
+    frame_buf: &'b mut [u8],
+    keyframe_interval: usize,
+    frame: usize,
+}
+
+impl<'b, R: Read> DeltaDecoder<'b, R> {
+    pub fn new(decoder: R, frame_buf: &'b mut [u8], keyframe_interval: usize) -> Self {
+        DeltaDecoder {
+            decoder,
+            frame_buf,
+            keyframe_interval: keyframe_interval.max(1),
+            frame: 0,
+        }
+    }
+
+    // Decodes the next frame into the frame buffer, returns false at the end of the stream
+    pub fn read_frame(&mut self) -> Result<bool, ReadExactError<R::Error>> {
+        if self.frame.is_multiple_of(self.keyframe_interval) {
+            if !read_frame_start(&mut self.decoder, self.frame_buf)? {
+                return Ok(false);
             }
-            Some(ReadState::Repeat { byte, ref mut len }) => {
-                let to_be_written = buf.len().min(*len);
-                buf[0..to_be_written].fill(byte);
+        } else {
+            let mut chunk = [0; 64];
 
-                if to_be_written >= *len {
-                    self.state = None;
-                } else {
-                    *len -= to_be_written;
+            for (i, prev) in self.frame_buf.chunks_mut(chunk.len()).enumerate() {
+                if i > 0 {
+                    self.decoder.read_exact(&mut chunk[..prev.len()])?;
+                } else if !read_frame_start(&mut self.decoder, &mut chunk[..prev.len()])? {
+                    return Ok(false);
+                }
+                for (prev, delta) in prev.iter_mut().zip(&chunk) {
+                    *prev ^= delta;
+                }
+            }
+        }
+
+        self.frame += 1;
+        Ok(true)
+    }
+
+    pub fn frame(&self) -> &[u8] {
+        self.frame_buf
+    }
+
+    // Index of the next frame read_frame will decode
+    pub fn position(&self) -> usize {
+        self.frame
+    }
+
+    // The underlying RLE decoder, for streams that mix in non-delta data
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.decoder
+    }
+}
+
+impl DeltaDecoder<'_, SeekableDecoder<'_, '_>> {
+    // Jumps to the keyframe before `frame` and decodes forward to it, the index stride must be the keyframe interval
+    pub fn seek_frame(&mut self, frame: usize) -> bool {
+        let keyframe = frame - frame % self.keyframe_interval;
+        if !self.decoder.seek_frame(keyframe) {
+            return false;
+        }
+
+        self.frame = keyframe;
+        while self.frame < frame {
+            if !matches!(self.read_frame(), Ok(true)) {
+                return false;
+            }
+        }
+
+        true
+    }
+}
+
+// Gray to raw Rgb565 the way the player shows it, every channel at the same level
+pub const GRAY_TO_RGB565: [u16; 256] = {
+    let mut table = [0; 256];
+    let mut gray = 0;
+    while gray < 256 {
+        table[gray] = (gray as u16 >> 3) << 11 | (gray as u16 >> 2) << 5 | gray as u16 >> 3;
+        gray += 1;
+    }
+    table
+};
+
+// Yields gray bytes from a Decoder, or an already decoded frame, as Rgb565 pixels ready for a display's
+// fill_contiguous or draw_iter. Iteration stops at the end of the stream or on the first read error,
+// which is kept for take_error.
+#[cfg(feature = "graphics")]
+pub struct PixelDecoder<R: ErrorType> {
+    reader: R,
+    buf: [u8; 64],
+    pos: usize,
+    len: usize,
+    error: Option<R::Error>,
+}
+
+#[cfg(feature = "graphics")]
+impl<R: Read> PixelDecoder<R> {
+    pub fn new(reader: R) -> PixelDecoder<R> {
+        PixelDecoder {
+            reader,
+            buf: [0; 64],
+            pos: 0,
+            len: 0,
+            error: None,
+        }
+    }
+
+    pub fn take_error(&mut self) -> Option<R::Error> {
+        self.error.take()
+    }
+}
+
+#[cfg(feature = "graphics")]
+impl<R: Read> Iterator for PixelDecoder<R> {
+    type Item = Rgb565;
+
+    fn next(&mut self) -> Option<Rgb565> {
+        if self.pos == self.len {
+            if self.error.is_some() {
+                return None;
+            }
+
+            match self.reader.read(&mut self.buf) {
+                Ok(0) => return None,
+                Ok(len) => (self.pos, self.len) = (0, len),
+                Err(err) => {
+                    self.error = Some(err);
+                    return None;
                 }
+            }
+        }
+
+        self.pos += 1;
+        Some(RawU16::new(GRAY_TO_RGB565[self.buf[self.pos - 1] as usize]).into())
+    }
+}
+
+#[cfg(feature = "async")]
+mod async_impls {
+    use super::*;
+    use embedded_io_async::{Read, Write};
+
+    impl<W: Write> Encoder<W> {
+        async fn write_state_async(&mut self, state: Option<WriteState>) -> Result<(), W::Error> {
+            if let Some(state) = state {
+                let mut header = [0; MAX_HEADER_LEN];
+                let len = state.header(self.format, &mut header);
+                self.writer.write_all(&header[..len]).await?;
+                self.writer.write_all(state.payload()).await?;
+                self.stats.record(&state, self.format);
+            }
+            Ok(())
+        }
+
+        pub async fn finalize_async(mut self) -> Result<W, W::Error> {
+            Write::flush(&mut self).await?;
+            Ok(self.writer)
+        }
+    }
+
+    impl<W: Write> Write for Encoder<W> {
+        async fn write(&mut self, buf: &[u8]) -> Result<usize, W::Error> {
+            for &new_byte in buf {
+                let state = self.push(new_byte);
+                self.write_state_async(state).await?;
+            }
+            Ok(buf.len())
+        }
+
+        async fn flush(&mut self) -> Result<(), W::Error> {
+            let state = self.state.take();
+            self.write_state_async(state).await?;
+            self.writer.flush().await
+        }
+    }
+
+    impl<R: Read> Decoder<R> {
+        async fn read_state_async(&mut self) -> Result<(), DecodeError<R::Error>> {
+            let mut header = RunHeader::new(self.format);
+            let mut first = true;
+            let run = loop {
+                let mut byte = 0;
+                match self.reader.read_exact(slice::from_mut(&mut byte)).await {
+                    Ok(_) => {}
+                    Err(ReadExactError::UnexpectedEof) if first => {
+                        self.state = None;
+                        return Ok(());
+                    }
+                    Err(ReadExactError::UnexpectedEof) => return Err(DecodeError::TruncatedStream),
+                    Err(ReadExactError::Other(err)) => return Err(DecodeError::Read(err)),
+                }
+                first = false;
+
+                if let Some(run) = header.push(byte).map_err(DecodeError::InvalidHeader)? {
+                    break run;
+                }
+            };
+
+            let mut state = ReadState::new(run);
+            self.reader
+                .read_exact(state.payload_mut())
+                .await
+                .map_err(|err| match err {
+                    ReadExactError::UnexpectedEof => DecodeError::TruncatedStream,
+                    ReadExactError::Other(err) => DecodeError::Read(err),
+                })?;
+            self.state = Some(state);
+
+            Ok(())
+        }
+    }
 
-                Ok(to_be_written)
+    impl<R: Read> Read for Decoder<R> {
+        async fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+            if self.state.is_none() {
+                self.read_state_async().await?;
             }
+
+            Ok(self.serve(buf))
         }
     }
 }
@@ -227,7 +908,8 @@ impl<R: Read> Read for Decoder<R> {
 #[cfg(feature = "std")]
 mod std_impls {
     use super::*;
-    use std::io::{self, Read, Write};
+    use std::io::{self, BufRead, Read, Seek, SeekFrom, Write};
+    use std::vec::Vec;
     
     impl<W> Write for Encoder<W>
         where Self: embedded_io::Write + ErrorType<Error = io::Error> {
@@ -241,9 +923,98 @@ mod std_impls {
     }
     
     impl<W> Read for Decoder<W>
-    where Self: embedded_io::Read + ErrorType<Error = io::Error> {
+    where Self: embedded_io::Read + ErrorType<Error = DecodeError<io::Error>> {
         fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
-            embedded_io::Read::read(self, buf)
+            embedded_io::Read::read(self, buf).map_err(io::Error::from)
+        }
+    }
+    
+    // Repeat runs have no buffer of their own, fill_buf hands out a slice of one of these instead
+    const REPEATED_LEN: usize = 64;
+    static REPEATED: [[u8; REPEATED_LEN]; 256] = {
+        let mut table = [[0; REPEATED_LEN]; 256];
+        let mut byte = 0;
+        while byte < 256 {
+            table[byte] = [byte as u8; REPEATED_LEN];
+            byte += 1;
+        }
+        table
+    };
+    
+    // Buffers nothing on top of the decoder, every fill_buf returns what is left of the current run
+    impl<R: embedded_io::Read<Error = io::Error>> BufRead for Decoder<R> {
+        fn fill_buf(&mut self) -> io::Result<&[u8]> {
+            if self.state.is_none() {
+                self.read_state()?;
+            }
+            
+            Ok(match &self.state {
+                None => &[],
+                Some(ReadState::Literal { bytes, len, pos }) => &bytes[*pos..*len],
+                Some(ReadState::Repeat { byte, len }) => &REPEATED[*byte as usize][..(*len).min(REPEATED_LEN)],
+            })
+        }
+        
+        fn consume(&mut self, amt: usize) {
+            self.advance(amt);
+        }
+    }
+    
+    // Seekable reader over an RLE stream together with its frame index, see Decoder::new_indexed
+    pub struct IndexedRead<R> {
+        reader: R,
+        start: u64,
+        index: Vec<FramePosition>,
+        step: u64,
+    }
+    
+    impl<R: Read> ErrorType for IndexedRead<R> { type Error = io::Error; }
+    
+    impl<R: Read> embedded_io::Read for IndexedRead<R> {
+        fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> { self.reader.read(buf) }
+    }
+    
+    impl<R: Read + Seek> Decoder<IndexedRead<R>> {
+        // `reader` has to be at the start of the RLE stream and `index` built by index_frames over the same
+        // stream with the same `frame_len` and `stride`
+        pub fn new_indexed(mut reader: R, index: Vec<FramePosition>, frame_len: usize, stride: usize) -> io::Result<Self> {
+            let start = reader.stream_position()?;
+            let step = (frame_len * stride.max(1)) as u64;
+            Ok(Self::new(IndexedRead { reader, start, index, step }))
+        }
+    }
+    
+    // Jumps to the closest indexed frame before the target and skips forward from there. Seeking past
+    // the end stops at the end, the decoded length isn't known up front so SeekFrom::End is unsupported.
+    impl<R: Read + Seek> Seek for Decoder<IndexedRead<R>> {
+        fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+            let target = match pos {
+                SeekFrom::Start(target) => Some(target),
+                SeekFrom::Current(offset) => self.position.checked_add_signed(offset),
+                SeekFrom::End(_) => return Err(io::Error::new(io::ErrorKind::Unsupported, "RLE streams can't seek from the end")),
+            };
+            let target = target.ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "seek before the start of the stream"))?;
+            
+            let indexed = self.reader.index.len().min((target / self.reader.step.max(1)) as usize + 1);
+            let (position, base) = match indexed.checked_sub(1).map(|entry| (entry, self.reader.index[entry])) {
+                Some((entry, position)) => (position, entry as u64 * self.reader.step),
+                None => (FramePosition::default(), 0),
+            };
+            
+            self.reader.reader.seek(SeekFrom::Start(self.reader.start + position.offset as u64))?;
+            self.state = None;
+            self.position = base - position.skip as u64;
+            self.skip_bytes((target - self.position) as usize)?;
+            Ok(self.position)
+        }
+    }
+    
+    impl From<DecodeError<io::Error>> for io::Error {
+        fn from(err: DecodeError<io::Error>) -> Self {
+            match err {
+                DecodeError::Read(err) => err,
+                err => io::Error::new(io::ErrorKind::InvalidData, err),
+            }
         }
     }
     
@@ -315,5 +1086,264 @@ mod tests {
 
             assert_eq!(&decoded[..], case);
         }
+
+        // Literal run cut off after its first byte
+        let mut dec = Decoder::new(&[0x02, 1][..]);
+        assert_eq!(dec.read(&mut [0; 4]), Err(DecodeError::TruncatedStream));
+    }
+
+    #[test]
+    fn test_varint() {
+        const FRAME_LEN: usize = 128 * 160;
+        let video = include_bytes!("../../assets/XD.raw");
+
+        let encode = |format| {
+            let mut enc = Encoder::new(Vec::new()).with_format(format);
+            enc.write_all(video).unwrap();
+            enc.finalize().unwrap()
+        };
+        let classic = encode(RunFormat::Classic);
+        let encoded = encode(RunFormat::Varint);
+        assert!(encoded.len() < classic.len());
+
+        let mut enc = Encoder::new(Vec::new()).with_format(RunFormat::Varint);
+        enc.write_all(video).unwrap();
+        let stats = enc.stats();
+        assert_eq!(stats.bytes_in, video.len() as u64);
+        assert_eq!(stats.bytes_out, encoded.len() as u64);
+        assert!(stats.longest_run > 128 && stats.repeat_runs > stats.literal_runs);
+        assert_eq!(stats, { enc.flush().unwrap(); enc.stats() });
+
+        let mut decoded = std::vec![0; video.len()];
+        Decoder::new(&encoded[..]).with_format(RunFormat::Varint).read_exact(&mut decoded).unwrap();
+        assert_eq!(&decoded[..], &video[..]);
+
+        // A black frame is one run
+        let mut black = [0; 8];
+        let len = {
+            let mut enc = Encoder::new(&mut black[..]).with_format(RunFormat::Varint);
+            enc.write_all(&[0; FRAME_LEN]).unwrap();
+            8 - enc.finalize().unwrap().len()
+        };
+        assert_eq!(&black[..len], [0xFF, 0xBF, 0x02, 0]);
+
+        let mut index = [FramePosition::default(); 8];
+        let len = index_frames(&encoded, RunFormat::Varint, FRAME_LEN, 5, &mut index);
+        let mut decoder = SeekableDecoder::new(&encoded, FRAME_LEN, 5, &index[..len]).with_format(RunFormat::Varint);
+        let mut frame = std::vec![0; FRAME_LEN];
+        for target in [7, 36, 0, 12] {
+            assert!(decoder.seek_frame(target));
+            decoder.read_exact(&mut frame).unwrap();
+            assert_eq!(&frame[..], &video[target * FRAME_LEN..][..FRAME_LEN]);
+        }
+
+        let mut dec = Decoder::new(&[0xFF, 0xFF, 0xFF, 0xFF, 0x1F, 0][..]).with_format(RunFormat::Varint);
+        assert_eq!(dec.read(&mut frame), Err(DecodeError::InvalidHeader(0x1F)));
+        let mut dec = Decoder::new(&[0x80][..]).with_format(RunFormat::Varint);
+        assert_eq!(dec.read(&mut frame), Err(DecodeError::TruncatedStream));
+    }
+
+    #[test]
+    fn test_seek() {
+        const FRAME_LEN: usize = 128 * 160;
+        let video = include_bytes!("../../assets/XD.raw");
+        let frames = video.len() / FRAME_LEN;
+
+        let mut enc = Encoder::new(Vec::new());
+        enc.write_all(video).unwrap();
+        let encoded = enc.finalize().unwrap();
+
+        let mut frame = std::vec![0; FRAME_LEN];
+        for stride in [1, 4] {
+            let mut index = [FramePosition::default(); 64];
+            let len = index_frames(&encoded, RunFormat::Classic, FRAME_LEN, stride, &mut index);
+            assert_eq!(len, frames.div_ceil(stride));
+
+            let mut decoder = SeekableDecoder::new(&encoded, FRAME_LEN, stride, &index[..len]);
+            for target in [5, 36, 0, 17, 18, 3] {
+                assert!(decoder.seek_frame(target));
+                decoder.read_exact(&mut frame).unwrap();
+                assert_eq!(&frame[..], &video[target * FRAME_LEN..][..FRAME_LEN]);
+            }
+
+            assert!(!decoder.seek_frame(frames + 4));
+        }
+
+        let mut decoder = Decoder::new(&encoded[..]);
+        assert_eq!(decoder.skip_bytes(100), Ok(100));
+        assert_eq!(decoder.skip_frames(2, FRAME_LEN), Ok(2));
+        assert_eq!(decoder.skip_bytes(FRAME_LEN - 100), Ok(FRAME_LEN - 100));
+        decoder.read_exact(&mut frame).unwrap();
+        assert_eq!(&frame[..], &video[3 * FRAME_LEN..][..FRAME_LEN]);
+        assert_eq!(decoder.skip_frames(frames, FRAME_LEN), Ok(frames - 4));
+        assert_eq!(decoder.skip_bytes(1), Ok(0));
+        assert_eq!(Decoder::new(&encoded[..]).skip_frames(frames, FRAME_LEN + 1), Err(DecodeError::TruncatedStream));
+    }
+
+    #[test]
+    fn test_delta() {
+        const FRAME_LEN: usize = 128 * 160;
+        let video = include_bytes!("../../assets/XD.raw");
+
+        let mut prev = std::vec![0; FRAME_LEN];
+        let mut enc = DeltaEncoder::new(Encoder::new(Vec::new()), &mut prev, 10);
+        for frame in video.chunks_exact(FRAME_LEN) {
+            enc.write_frame(frame).unwrap();
+        }
+        let encoded = enc.finalize().unwrap();
+
+        let mut frame_buf = std::vec![0; FRAME_LEN];
+        let mut dec = DeltaDecoder::new(Decoder::new(&encoded[..]), &mut frame_buf, 10);
+        for frame in video.chunks_exact(FRAME_LEN) {
+            assert!(dec.read_frame().unwrap());
+            assert_eq!(dec.frame(), frame);
+        }
+        assert!(!dec.read_frame().unwrap());
+
+        // Detailed, mostly static scene with a small moving box is where delta frames pay off
+        let size = |delta: bool| {
+            let background = &video[..FRAME_LEN];
+            let mut prev = std::vec![0; FRAME_LEN];
+            let mut enc = DeltaEncoder::new(Encoder::new(Vec::new()), &mut prev, if delta { 10 } else { 1 });
+            for i in 0..20 {
+                let mut frame = background.to_vec();
+                for row in frame.chunks_exact_mut(160).skip(50).take(16) {
+                    row[i * 4..i * 4 + 16].fill(0);
+                }
+                enc.write_frame(&frame).unwrap();
+            }
+            enc.finalize().unwrap().len()
+        };
+        assert!(size(true) * 4 < size(false));
+
+        let mut index = [FramePosition::default(); 8];
+        let len = index_frames(&encoded, RunFormat::Classic, FRAME_LEN, 10, &mut index);
+        let seekable = SeekableDecoder::new(&encoded, FRAME_LEN, 10, &index[..len]);
+        let mut dec = DeltaDecoder::new(seekable, &mut frame_buf, 10);
+        for target in [23, 5, 30, 36] {
+            assert!(dec.seek_frame(target));
+            assert!(dec.read_frame().unwrap());
+            assert_eq!(dec.frame(), &video[target * FRAME_LEN..][..FRAME_LEN]);
+            assert_eq!(dec.position(), target + 1);
+        }
+
+        // A keyframe cut short is an error, only a stream ending between frames is a clean end
+        let mut frame_buf = [0; 4];
+        let mut dec = DeltaDecoder::new(Decoder::new(&[0x83, 1, 0x81, 2][..]), &mut frame_buf, 1);
+        assert_eq!(dec.read_frame(), Ok(true));
+        assert_eq!(dec.read_frame(), Err(ReadExactError::UnexpectedEof));
+        // Same for a delta frame that ends inside its first chunk
+        let mut dec = DeltaDecoder::new(Decoder::new(&[0x83, 1, 0x81, 0][..]), &mut frame_buf, 2);
+        assert_eq!(dec.read_frame(), Ok(true));
+        assert_eq!(dec.read_frame(), Err(ReadExactError::UnexpectedEof));
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_std() {
+        use std::io::{BufRead, Cursor, Seek, SeekFrom};
+
+        const FRAME_LEN: usize = 128 * 160;
+        let video = include_bytes!("../../assets/XD.raw");
+        let mut enc = Encoder::new(Vec::new()).with_format(RunFormat::Varint);
+        enc.write_all(video).unwrap();
+        let encoded = enc.finalize().unwrap();
+
+        let mut decoded = Vec::new();
+        std::io::copy(&mut Decoder::new_std(&encoded[..]).with_format(RunFormat::Varint), &mut decoded).unwrap();
+        assert_eq!(&decoded[..], &video[..]);
+
+        let text = b"aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa\nline two\n\nend";
+        let mut enc = Encoder::new(Vec::new());
+        enc.write_all(text).unwrap();
+        let lines: Vec<_> = Decoder::new_std(&enc.finalize().unwrap()[..]).lines().map(Result::unwrap).collect();
+        assert_eq!(lines, ["a".repeat(86), "line two".into(), "".into(), "end".into()]);
+
+        let mut index = std::vec![FramePosition::default(); 8];
+        let len = index_frames(&encoded, RunFormat::Varint, FRAME_LEN, 5, &mut index);
+        index.truncate(len);
+        let mut file = Cursor::new([&[0xAA; 7][..], &encoded].concat());
+        file.set_position(7);
+        let mut decoder = Decoder::new_indexed(file, index, FRAME_LEN, 5).unwrap().with_format(RunFormat::Varint);
+        let mut frame = std::vec![0; FRAME_LEN];
+        for target in [7, 36, 0, 12] {
+            assert_eq!(decoder.seek(SeekFrom::Start((target * FRAME_LEN) as u64)).unwrap(), (target * FRAME_LEN) as u64);
+            std::io::Read::read_exact(&mut decoder, &mut frame).unwrap();
+            assert_eq!(&frame[..], &video[target * FRAME_LEN..][..FRAME_LEN]);
+        }
+        assert_eq!(decoder.seek(SeekFrom::Current(-100)).unwrap(), (13 * FRAME_LEN - 100) as u64);
+        assert_eq!(decoder.fill_buf().unwrap()[0], video[13 * FRAME_LEN - 100]);
+        assert_eq!(decoder.seek(SeekFrom::Start(u32::MAX as u64)).unwrap(), video.len() as u64);
+        assert!(decoder.seek(SeekFrom::End(0)).is_err());
+    }
+
+    #[cfg(feature = "async")]
+    #[test]
+    fn test_async() {
+        use core::pin::pin;
+        use core::task::{Context, Poll, Waker};
+
+        // Slices and Vecs never pend, so polling once is enough
+        fn block_on<F: Future>(future: F) -> F::Output {
+            match pin!(future).poll(&mut Context::from_waker(Waker::noop())) {
+                Poll::Ready(output) => output,
+                Poll::Pending => unreachable!(),
+            }
+        }
+
+        let video = include_bytes!("../../assets/XD.raw");
+        let encoded = block_on(async {
+            let mut enc = Encoder::new(Vec::new());
+            embedded_io_async::Write::write_all(&mut enc, video).await.unwrap();
+            enc.finalize_async().await.unwrap()
+        });
+
+        let mut sync = Encoder::new(Vec::new());
+        sync.write_all(video).unwrap();
+        assert_eq!(encoded, sync.finalize().unwrap());
+
+        let mut decoded = std::vec![0; video.len()];
+        block_on(async {
+            let mut dec = Decoder::new(&encoded[..]);
+            embedded_io_async::Read::read_exact(&mut dec, &mut decoded).await.unwrap();
+        });
+        assert_eq!(&decoded[..], &video[..]);
+    }
+
+    #[test]
+    fn test_slice() {
+        let frame = &include_bytes!("../../assets/XD.raw")[..128 * 160];
+        let mut compressed = [0; 128 * 160];
+        let mut restored = [0; 128 * 160];
+
+        let len = encode_to_slice(frame, &mut compressed).unwrap();
+        assert!(len < frame.len());
+        assert_eq!(decode_to_slice(&compressed[..len], &mut restored), frame.len());
+        assert_eq!(&restored[..], frame);
+
+        assert_eq!(encode_to_slice(frame, &mut compressed[..len - 1]), Err(SliceWriteError::Full));
+        assert_eq!(decode_to_slice(&compressed[..len], &mut restored[..100]), 100);
+    }
+
+    #[cfg(feature = "graphics")]
+    #[test]
+    fn test_pixels() {
+        use embedded_graphics_core::pixelcolor::raw::RawData;
+
+        assert_eq!(GRAY_TO_RGB565[0], 0);
+        assert_eq!(GRAY_TO_RGB565[255], 0xFFFF);
+        assert_eq!(GRAY_TO_RGB565[0x80], 0x8410);
+
+        let frame = &include_bytes!("../../assets/XD.raw")[..128 * 160];
+        let mut compressed = [0; 128 * 160];
+        let len = encode_to_slice(frame, &mut compressed).unwrap();
+        let pixels: Vec<_> = PixelDecoder::new(Decoder::new(&compressed[..len])).collect();
+        assert!(pixels.iter().zip(frame).all(|(&pixel, &gray)| RawU16::from(pixel).into_inner() == GRAY_TO_RGB565[gray as usize]));
+        assert_eq!(pixels[0], Rgb565::new(frame[0] >> 3, frame[0] >> 2, frame[0] >> 3));
+        assert_eq!(pixels.len(), frame.len());
+
+        let mut truncated = PixelDecoder::new(Decoder::new(&[0x84, 7, 0x02, 1][..]));
+        assert_eq!(truncated.by_ref().count(), 5);
+        assert_eq!(truncated.take_error(), Some(DecodeError::TruncatedStream));
     }
 }
@@ -0,0 +1,223 @@
+use core::slice;
+use embedded_io::{Read, ReadExactError, Write};
+use crate::rle::DecodeError;
+
+
+// Same run layout as `rle`, but over little-endian u16 words, so Rgb565 frames can be stored as is
+// and decoded straight into a framebuffer. A header below 0x80 is followed by header + 2 literal
+// words, otherwise the next word repeats (header & 0x7F) + 1 times.
+// Literal words live next to the state, a literal run is written out before the buffer is reused.
+enum WriteState {
+    Repeat { len: u8, word: u16 },
+    Literal { len: u8 },
+}
+
+pub struct Encoder<W> {
+    writer: W,
+    state: Option<WriteState>,
+    words: [u16; 129],
+}
+
+impl<W: Write> Encoder<W> {
+    pub fn new(writer: W) -> Encoder<W> {
+        Encoder {
+            writer,
+            state: None,
+            words: [0; 129],
+        }
+    }
+
+    fn write_state(&mut self, state: Option<WriteState>) -> Result<(), W::Error> {
+        match state {
+            None => {}
+            Some(WriteState::Repeat { len, word }) => {
+                self.writer.write_all(&[0x80 | (len - 1)])?;
+                self.writer.write_all(&word.to_le_bytes())?;
+            }
+            Some(WriteState::Literal { len }) => {
+                self.writer.write_all(&[len - 2])?;
+                for word in &self.words[..len as usize] {
+                    self.writer.write_all(&word.to_le_bytes())?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn push(&mut self, word: u16) -> Option<WriteState> {
+        match self.state {
+            None => self.state.replace(WriteState::Repeat { len: 1, word }),
+            // Append to Repeat
+            Some(WriteState::Repeat { len: ref mut len @ ..128, word: prev }) if prev == word => {
+                *len += 1;
+                None
+            }
+            // Transform singleton repeat into Literal
+            Some(WriteState::Repeat { len: 1, word: prev }) => {
+                self.words[0] = prev;
+                self.words[1] = word;
+                self.state = Some(WriteState::Literal { len: 2 });
+                None
+            }
+            // Split the last word off the Literal and flush the rest
+            Some(WriteState::Literal { len: ref mut len @ 2.. }) if self.words[*len as usize - 1] == word => {
+                if *len > 2 {
+                    *len -= 1;
+                } else {
+                    self.state = Some(WriteState::Repeat { len: 1, word: self.words[0] });
+                }
+                self.state.replace(WriteState::Repeat { len: 2, word })
+            }
+            // Append to Literal
+            Some(WriteState::Literal { len: ref mut len @ ..129 }) => {
+                self.words[*len as usize] = word;
+                *len += 1;
+                None
+            }
+            // Flush and start new Repeat
+            _ => self.state.replace(WriteState::Repeat { len: 1, word }),
+        }
+    }
+
+    pub fn write_pixels(&mut self, pixels: &[u16]) -> Result<(), W::Error> {
+        for &word in pixels {
+            let state = self.push(word);
+            self.write_state(state)?;
+        }
+        Ok(())
+    }
+
+    pub fn finalize(mut self) -> Result<W, W::Error> {
+        let state = self.state.take();
+        self.write_state(state)?;
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+}
+
+enum ReadState {
+    Repeat { len: usize, word: u16 },
+    Literal { len: usize, pos: usize },
+}
+
+pub struct Decoder<R> {
+    reader: R,
+    state: Option<ReadState>,
+    bytes: [u8; 258],
+}
+
+impl<R: Read> Decoder<R> {
+    pub fn new(reader: R) -> Decoder<R> {
+        Decoder {
+            reader,
+            state: None,
+            bytes: [0; 258],
+        }
+    }
+
+    fn read_state(&mut self) -> Result<(), DecodeError<R::Error>> {
+        let map_err = |err| match err {
+            ReadExactError::UnexpectedEof => DecodeError::TruncatedStream,
+            ReadExactError::Other(err) => DecodeError::Read(err),
+        };
+
+        let mut header = 0;
+        match self.reader.read_exact(slice::from_mut(&mut header)) {
+            Ok(_) => {}
+            Err(ReadExactError::UnexpectedEof) => return Ok(()),
+            Err(ReadExactError::Other(err)) => return Err(DecodeError::Read(err)),
+        }
+
+        if header < 0x80 {
+            let len = header as usize + 2;
+            self.reader.read_exact(&mut self.bytes[..len * 2]).map_err(map_err)?;
+            self.state = Some(ReadState::Literal { len, pos: 0 });
+        } else {
+            let mut word = [0; 2];
+            self.reader.read_exact(&mut word).map_err(map_err)?;
+            self.state = Some(ReadState::Repeat { len: (header & 0x7F) as usize + 1, word: u16::from_le_bytes(word) });
+        }
+
+        Ok(())
+    }
+
+    // Decodes up to `buf.len()` pixels, returns how many were written, 0 at the end of the stream
+    pub fn read(&mut self, buf: &mut [u16]) -> Result<usize, DecodeError<R::Error>> {
+        if self.state.is_none() {
+            self.read_state()?;
+        }
+
+        let (written, done) = match self.state {
+            None => return Ok(0),
+            Some(ReadState::Repeat { ref mut len, word }) => {
+                let written = buf.len().min(*len);
+                buf[..written].fill(word);
+                *len -= written;
+                (written, *len == 0)
+            }
+            Some(ReadState::Literal { len, ref mut pos }) => {
+                let written = buf.len().min(len - *pos);
+                for (out, word) in buf[..written].iter_mut().zip(self.bytes[*pos * 2..].chunks_exact(2)) {
+                    *out = u16::from_le_bytes([word[0], word[1]]);
+                }
+                *pos += written;
+                (written, *pos == len)
+            }
+        };
+
+        if done {
+            self.state = None;
+        }
+        Ok(written)
+    }
+
+    // Fills `frame` completely, returns false if the stream ended cleanly before it
+    pub fn read_frame(&mut self, frame: &mut [u16]) -> Result<bool, DecodeError<R::Error>> {
+        let mut len = 0;
+        while len < frame.len() {
+            match self.read(&mut frame[len..])? {
+                0 if len == 0 => return Ok(false),
+                0 => return Err(DecodeError::TruncatedStream),
+                read => len += read,
+            }
+        }
+
+        Ok(true)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::vec::Vec;
+
+    #[test]
+    fn test_rle16() {
+        const FRAME_LEN: usize = 128 * 160;
+        // Grayscale spread over all three channels, like the player does, plus a few odd words
+        let mut video: Vec<u16> = include_bytes!("../../assets/XD.raw")
+            .iter()
+            .map(|&gray| (gray as u16 >> 3) << 11 | (gray as u16 >> 2) << 5 | gray as u16 >> 3)
+            .collect();
+        video[..8].copy_from_slice(&[0xFFFF, 0x00FF, 0xFF00, 0xFF00, 1, 2, 2, 2]);
+
+        let mut encoder = Encoder::new(Vec::new());
+        for frame in video.chunks_exact(FRAME_LEN) {
+            encoder.write_pixels(frame).unwrap();
+        }
+        let encoded = encoder.finalize().unwrap();
+        assert!(encoded.len() < video.len() * 2);
+
+        let mut decoder = Decoder::new(&encoded[..]);
+        let mut frame = std::vec![0; FRAME_LEN];
+        for expected in video.chunks_exact(FRAME_LEN) {
+            assert_eq!(decoder.read_frame(&mut frame), Ok(true));
+            assert_eq!(&frame[..], expected);
+        }
+        assert_eq!(decoder.read_frame(&mut frame), Ok(false));
+
+        assert_eq!(Decoder::new(&encoded[..encoded.len() - 1]).read_frame(&mut video), Err(DecodeError::TruncatedStream));
+        assert_eq!(Decoder::new(&[0x81, 0x34, 0x12][..]).read_frame(&mut frame[..3]), Err(DecodeError::TruncatedStream));
+    }
+}
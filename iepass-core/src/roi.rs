@@ -0,0 +1,173 @@
+use core::fmt;
+use embedded_io::{Read, ReadExactError, Write};
+
+
+// Region-of-interest frames: each frame is an 8 byte header (x, y, width, height as u16 LE) followed
+// by only the pixels inside that rectangle, row by row. Meant to be fed through the RLE encoder.
+// An empty region means nothing changed since the previous frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Region {
+    pub x: u16,
+    pub y: u16,
+    pub width: u16,
+    pub height: u16,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Error<E> {
+    Read(E),
+    Truncated,
+    // Region doesn't fit in the frame buffer passed to read_frame
+    TooLarge(Region),
+}
+
+impl Region {
+    pub const EMPTY: Region = Region { x: 0, y: 0, width: 0, height: 0 };
+
+    pub fn full(width: u16, height: u16) -> Region {
+        Region { x: 0, y: 0, width, height }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.width == 0 || self.height == 0
+    }
+
+    pub fn area(&self) -> usize {
+        self.width as usize * self.height as usize
+    }
+
+    fn to_bytes(self) -> [u8; 8] {
+        let mut bytes = [0; 8];
+        for (chunk, value) in bytes.chunks_exact_mut(2).zip([self.x, self.y, self.width, self.height]) {
+            chunk.copy_from_slice(&value.to_le_bytes());
+        }
+        bytes
+    }
+
+    fn from_bytes(bytes: [u8; 8]) -> Region {
+        let value = |i: usize| u16::from_le_bytes([bytes[i], bytes[i + 1]]);
+        Region { x: value(0), y: value(2), width: value(4), height: value(6) }
+    }
+}
+
+impl<E: fmt::Debug> fmt::Display for Error<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Read(err) => write!(f, "Failed to read ROI frame: {err:?}"),
+            Error::Truncated => write!(f, "Truncated ROI frame"),
+            Error::TooLarge(region) => write!(f, "ROI frame region {region:?} is larger than the frame"),
+        }
+    }
+}
+
+impl<E: fmt::Debug> core::error::Error for Error<E> {}
+
+// Bounding box of the pixels that differ between two frames of the given width
+pub fn diff(prev: &[u8], next: &[u8], width: usize) -> Region {
+    let mut min = (usize::MAX, usize::MAX);
+    let mut max = (0, 0);
+
+    for (y, (prev, next)) in prev.chunks_exact(width).zip(next.chunks_exact(width)).enumerate() {
+        let Some(left) = prev.iter().zip(next).position(|(a, b)| a != b) else { continue };
+        let right = width - 1 - prev.iter().zip(next).rev().position(|(a, b)| a != b).unwrap_or(0);
+
+        min = (min.0.min(left), min.1.min(y));
+        max = (max.0.max(right), max.1.max(y));
+    }
+
+    if min.0 == usize::MAX {
+        return Region::EMPTY;
+    }
+
+    Region {
+        x: min.0 as u16,
+        y: min.1 as u16,
+        width: (max.0 - min.0 + 1) as u16,
+        height: (max.1 - min.1 + 1) as u16,
+    }
+}
+
+pub fn write_frame<W: Write>(writer: &mut W, frame: &[u8], width: usize, region: Region) -> Result<(), W::Error> {
+    writer.write_all(&region.to_bytes())?;
+
+    for row in frame.chunks_exact(width).skip(region.y as usize).take(region.height as usize) {
+        writer.write_all(&row[region.x as usize..][..region.width as usize])?;
+    }
+
+    Ok(())
+}
+
+// Reads the next frame's region and its packed pixels into the start of `buf`, None at the end of the stream
+pub fn read_frame<R: Read>(reader: &mut R, buf: &mut [u8]) -> Result<Option<Region>, Error<R::Error>> {
+    let map_err = |err| match err {
+        ReadExactError::UnexpectedEof => Error::Truncated,
+        ReadExactError::Other(err) => Error::Read(err),
+    };
+
+    let mut header = [0; 8];
+    let mut len = 0;
+    while len < header.len() {
+        match reader.read(&mut header[len..]).map_err(Error::Read)? {
+            0 if len == 0 => return Ok(None),
+            0 => return Err(Error::Truncated),
+            read => len += read,
+        }
+    }
+
+    let region = Region::from_bytes(header);
+    let pixels = buf.get_mut(..region.area()).ok_or(Error::TooLarge(region))?;
+    reader.read_exact(pixels).map_err(map_err)?;
+
+    Ok(Some(region))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::vec::Vec;
+    use crate::rle;
+
+    const WIDTH: usize = 160;
+    const HEIGHT: usize = 128;
+
+    #[test]
+    fn test_roi() {
+        let video = include_bytes!("../../assets/XD.raw");
+        let mut encoder = rle::Encoder::new(Vec::new());
+        let mut prev: &[u8] = &[0; WIDTH * HEIGHT];
+
+        for frame in video.chunks_exact(WIDTH * HEIGHT) {
+            write_frame(&mut encoder, frame, WIDTH, diff(prev, frame, WIDTH)).unwrap();
+            prev = frame;
+        }
+        // Repeated frame, nothing changes
+        write_frame(&mut encoder, prev, WIDTH, diff(prev, prev, WIDTH)).unwrap();
+
+        let encoded = encoder.finalize().unwrap();
+        let mut decoder = rle::Decoder::new(&encoded[..]);
+        let mut screen = [0; WIDTH * HEIGHT];
+        let mut buf = [0; WIDTH * HEIGHT];
+        let mut frames = video.chunks_exact(WIDTH * HEIGHT);
+
+        while let Some(region) = read_frame(&mut decoder, &mut buf).unwrap() {
+            let pixels = &buf[..region.area()];
+            for (row, line) in pixels.chunks_exact(region.width.max(1) as usize).enumerate() {
+                let start = (region.y as usize + row) * WIDTH + region.x as usize;
+                screen[start..start + line.len()].copy_from_slice(line);
+            }
+
+            match frames.next() {
+                Some(frame) => assert_eq!(&screen[..], frame),
+                None => assert!(region.is_empty()),
+            }
+        }
+
+        assert_eq!(diff(&[0, 0, 0, 0, 0, 0], &[0, 0, 0, 0, 1, 0], 3), Region { x: 1, y: 1, width: 1, height: 1 });
+        assert_eq!(read_frame(&mut &[1, 0, 0][..], &mut buf), Err(Error::Truncated));
+        assert_eq!(
+            read_frame(&mut &Region::full(2, 2).to_bytes()[..], &mut buf[..3]),
+            Err(Error::TooLarge(Region::full(2, 2))),
+        );
+    }
+}
@@ -0,0 +1,165 @@
+const MINUTES_PER_DAY: u32 = 24 * 60;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Trigger {
+    // Minutes since midnight
+    At(u16),
+    Every(u16),
+    BatteryBelow(u8),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParseRuleError;
+
+// A rule like "at 09:00 show slideshow", "every 15 min sync ntp" or "at 20% battery dim"
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rule<'a> {
+    pub trigger: Trigger,
+    pub action: &'a str,
+    last_fired: Option<u32>,
+    armed: bool,
+}
+
+impl<'a> Rule<'a> {
+    pub fn parse(line: &'a str) -> Result<Rule<'a>, ParseRuleError> {
+        let (keyword, rest) = split_word(line.trim());
+        let (when, mut rest) = split_word(rest);
+
+        let trigger = match keyword {
+            "at" if when.ends_with('%') => {
+                let percent = when[..when.len() - 1].parse().map_err(|_| ParseRuleError)?;
+                let (battery, action) = split_word(rest);
+                if battery != "battery" {
+                    return Err(ParseRuleError);
+                }
+                rest = action;
+                Trigger::BatteryBelow(percent)
+            }
+            "at" => {
+                let (hour, minute) = when.split_once(':').ok_or(ParseRuleError)?;
+                let hour: u16 = hour.parse().map_err(|_| ParseRuleError)?;
+                let minute: u16 = minute.parse().map_err(|_| ParseRuleError)?;
+                if hour >= 24 || minute >= 60 {
+                    return Err(ParseRuleError);
+                }
+                Trigger::At(hour * 60 + minute)
+            }
+            "every" => {
+                let digits = when.find(|c: char| !c.is_ascii_digit()).unwrap_or(when.len());
+                let (count, mut unit) = when.split_at(digits);
+                if unit.is_empty() {
+                    (unit, rest) = split_word(rest);
+                }
+                let count: u16 = count.parse().map_err(|_| ParseRuleError)?;
+                let minutes = match unit {
+                    "m" | "min" | "mins" | "minute" | "minutes" => count,
+                    "h" | "hour" | "hours" => count.checked_mul(60).ok_or(ParseRuleError)?,
+                    _ => return Err(ParseRuleError),
+                };
+                if minutes == 0 {
+                    return Err(ParseRuleError);
+                }
+                Trigger::Every(minutes)
+            }
+            _ => return Err(ParseRuleError),
+        };
+
+        if rest.is_empty() {
+            return Err(ParseRuleError);
+        }
+
+        Ok(Rule {
+            trigger,
+            action: rest,
+            last_fired: None,
+            armed: true,
+        })
+    }
+
+    // `now` is minutes since epoch in local time, returns true when the action should run
+    pub fn poll(&mut self, now: u32, battery: Option<u8>) -> bool {
+        let fire = match self.trigger {
+            Trigger::At(minute) => now % MINUTES_PER_DAY == minute as u32 && self.last_fired != Some(now),
+            Trigger::Every(minutes) => self.last_fired.is_none_or(|last| now.wrapping_sub(last) >= minutes as u32),
+            Trigger::BatteryBelow(percent) => match battery {
+                Some(battery) if battery < percent => core::mem::replace(&mut self.armed, false),
+                Some(_) => {
+                    self.armed = true;
+                    false
+                }
+                None => false,
+            },
+        };
+
+        if fire {
+            self.last_fired = Some(now);
+        }
+
+        fire
+    }
+}
+
+fn split_word(s: &str) -> (&str, &str) {
+    match s.split_once(char::is_whitespace) {
+        Some((word, rest)) => (word, rest.trim_start()),
+        None => (s, ""),
+    }
+}
+
+// Parses a rule file, one rule per line, blank lines and lines starting with '#' are skipped
+pub fn parse(input: &str) -> impl Iterator<Item = Result<Rule<'_>, ParseRuleError>> {
+    input
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(Rule::parse)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::vec::Vec;
+
+    #[test]
+    fn test_schedule() {
+        let rules = parse("
+            # morning
+            at 09:00 show slideshow
+            every 15 min sync NTP
+            every 2h  backup
+            at 20% battery dim
+        ").collect::<Result<Vec<_>, _>>().unwrap();
+
+        let triggers: Vec<_> = rules.iter().map(|rule| (rule.trigger, rule.action)).collect();
+        assert_eq!(triggers, [
+            (Trigger::At(9 * 60), "show slideshow"),
+            (Trigger::Every(15), "sync NTP"),
+            (Trigger::Every(120), "backup"),
+            (Trigger::BatteryBelow(20), "dim"),
+        ]);
+
+        for line in ["at 24:00 x", "at 9:00", "every 0m x", "every 5 days x", "at 20% dim", "whenever x"] {
+            assert_eq!(Rule::parse(line), Err(ParseRuleError), "{line}");
+        }
+
+        let [mut at, mut every, _, mut battery] = rules.try_into().unwrap();
+        let day = 20_000 * MINUTES_PER_DAY;
+
+        assert!(!at.poll(day + 8 * 60 + 59, None));
+        assert!(at.poll(day + 9 * 60, None));
+        assert!(!at.poll(day + 9 * 60, None));
+        assert!(at.poll(day + MINUTES_PER_DAY + 9 * 60, None));
+
+        assert!(every.poll(day, None));
+        assert!(!every.poll(day + 14, None));
+        assert!(every.poll(day + 15, None));
+
+        assert!(!battery.poll(day, Some(50)));
+        assert!(battery.poll(day, Some(19)));
+        assert!(!battery.poll(day + 1, Some(18)));
+        assert!(!battery.poll(day + 2, Some(25)));
+        assert!(battery.poll(day + 3, Some(10)));
+        assert!(!battery.poll(day + 4, None));
+    }
+}
@@ -0,0 +1,713 @@
+use core::convert::Infallible;
+use core::fmt;
+use embedded_io::{ErrorKind, ErrorType, Read, ReadExactError, Write};
+use crate::crc32::crc32;
+use crate::rle::{self, RunFormat};
+use crate::roi;
+
+
+// .smol container: a fixed little-endian header followed by the encoded frames, then optionally
+// a CRC32 per frame (u32 LE each) over the pixels the codec hands to the player: the whole frame,
+// or just the region's pixels for ROI frames.
+// With an audio track the frames are interleaved, each one stored as a u32 LE video length, its
+// video runs, a u16 LE audio length and its samples. Video runs don't cross frames then, so the
+// video chunks concatenated are a plain RLE stream again.
+// A metadata section can sit between the header and the frames: its u16 LE length, then entries of
+// a tag byte, a length byte and the value.
+//  0..4   magic "SMOL"
+//  4      version
+//  5      pixel format
+//  6      codec
+//  7      frames per second
+//  8..10  width
+//  10..12 height
+//  12..16 frame count
+//  16..18 keyframe interval, only used by delta codecs
+//  18     flags
+//  19     audio codec, only used with FLAG_AUDIO
+//  20..22 audio sample rate
+// Versions only ever append fields, an older file's header ends earlier and what it lacks reads as zero,
+// so no flags and no audio. New features have to stay off when their bytes are zero.
+pub const MAGIC: [u8; 4] = *b"SMOL";
+pub const VERSION: u8 = 4;
+pub const HEADER_LEN: usize = 22;
+
+// Header length of each version, None for versions this build doesn't know
+fn header_len(version: u8) -> Option<usize> {
+    match version {
+        1 => Some(16),
+        2 => Some(18),
+        3 => Some(19),
+        VERSION => Some(HEADER_LEN),
+        _ => None,
+    }
+}
+
+// Run headers are rle::RunFormat::Varint instead of Classic
+pub const FLAG_VARINT_RUNS: u8 = 1 << 0;
+// The file ends with a CRC table
+pub const FLAG_FRAME_CRC: u8 = 1 << 1;
+// Frames are interleaved with audio chunks
+pub const FLAG_AUDIO: u8 = 1 << 2;
+// Metadata section before the frames
+pub const FLAG_METADATA: u8 = 1 << 3;
+const KNOWN_FLAGS: u8 = FLAG_VARINT_RUNS | FLAG_FRAME_CRC | FLAG_AUDIO | FLAG_METADATA;
+
+// UTF-8 clip name
+pub const TAG_TITLE: u8 = 1;
+// UTF-8 author
+pub const TAG_AUTHOR: u8 = 2;
+// Playing time in milliseconds, u32 LE
+pub const TAG_DURATION: u8 = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PixelFormat {
+    Gray8 = 0,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Codec {
+    // Whole frames through the RLE encoder
+    Rle = 0,
+    // roi frames through the RLE encoder
+    RleRoi = 1,
+    // rle::DeltaEncoder frames, with a keyframe every `keyframe_interval` frames
+    RleDelta = 2,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AudioCodec {
+    // Unsigned 8-bit mono PCM
+    Pcm8 = 0,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Audio {
+    pub codec: AudioCodec,
+    pub sample_rate: u16,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Error<E = Infallible> {
+    Read(E),
+    Truncated,
+    BadMagic,
+    UnsupportedVersion(u8),
+    UnknownPixelFormat(u8),
+    UnknownCodec(u8),
+    UnknownFlags(u8),
+    UnknownAudioCodec(u8),
+    // Interleaved frame whose lengths run past the end of the data
+    TruncatedChunk,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WriteError<E> {
+    Write(E),
+    // Value or section longer than its length field can hold, with the length it would have needed
+    TooLong(usize),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Header {
+    pub width: u16,
+    pub height: u16,
+    pub fps: u8,
+    pub frame_count: u32,
+    pub pixel_format: PixelFormat,
+    pub codec: Codec,
+    pub keyframe_interval: u16,
+    pub run_format: RunFormat,
+    pub frame_crc: bool,
+    pub audio: Option<Audio>,
+    pub metadata: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Entry<'a> {
+    pub tag: u8,
+    pub value: &'a [u8],
+}
+
+// Entries of a metadata section, unknown tags are kept and can be looked up like the rest
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Metadata<'a>(&'a [u8]);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FrameCrcs<'a>(&'a [u8]);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VerifyError {
+    // Frames or CRC table that couldn't be found
+    Container(Error),
+    // Frame that couldn't be decoded
+    Corrupt(u32),
+    // Frame that decoded but doesn't match its CRC
+    Mismatch(u32),
+    // Frame buffer passed to verify_frames is shorter than one frame
+    BufferTooSmall,
+}
+
+impl PixelFormat {
+    pub fn bytes_per_pixel(self) -> usize {
+        match self {
+            PixelFormat::Gray8 => 1,
+        }
+    }
+}
+
+impl Header {
+    pub fn new(width: u16, height: u16, fps: u8, frame_count: u32) -> Header {
+        Header {
+            width,
+            height,
+            fps,
+            frame_count,
+            pixel_format: PixelFormat::Gray8,
+            codec: Codec::Rle,
+            keyframe_interval: 1,
+            run_format: RunFormat::Classic,
+            frame_crc: false,
+            audio: None,
+            metadata: false,
+        }
+    }
+
+    pub fn with_codec(mut self, codec: Codec) -> Header {
+        self.codec = codec;
+        self
+    }
+
+    pub fn with_keyframe_interval(mut self, keyframe_interval: u16) -> Header {
+        self.keyframe_interval = keyframe_interval.max(1);
+        self
+    }
+
+    pub fn with_run_format(mut self, run_format: RunFormat) -> Header {
+        self.run_format = run_format;
+        self
+    }
+
+    pub fn with_frame_crc(mut self, frame_crc: bool) -> Header {
+        self.frame_crc = frame_crc;
+        self
+    }
+
+    pub fn with_audio(mut self, audio: Audio) -> Header {
+        self.audio = Some(audio);
+        self
+    }
+
+    pub fn with_metadata(mut self, metadata: bool) -> Header {
+        self.metadata = metadata;
+        self
+    }
+
+    // Size of one decoded frame in bytes
+    pub fn frame_len(&self) -> usize {
+        self.width as usize * self.height as usize * self.pixel_format.bytes_per_pixel()
+    }
+
+    pub fn to_bytes(&self) -> [u8; HEADER_LEN] {
+        let mut bytes = [0; HEADER_LEN];
+        bytes[0..4].copy_from_slice(&MAGIC);
+        bytes[4] = VERSION;
+        bytes[5] = self.pixel_format as u8;
+        bytes[6] = self.codec as u8;
+        bytes[7] = self.fps;
+        bytes[8..10].copy_from_slice(&self.width.to_le_bytes());
+        bytes[10..12].copy_from_slice(&self.height.to_le_bytes());
+        bytes[12..16].copy_from_slice(&self.frame_count.to_le_bytes());
+        bytes[16..18].copy_from_slice(&self.keyframe_interval.to_le_bytes());
+        if self.run_format == RunFormat::Varint {
+            bytes[18] |= FLAG_VARINT_RUNS;
+        }
+        if self.frame_crc {
+            bytes[18] |= FLAG_FRAME_CRC;
+        }
+        if let Some(audio) = self.audio {
+            bytes[18] |= FLAG_AUDIO;
+            bytes[19] = audio.codec as u8;
+            bytes[20..22].copy_from_slice(&audio.sample_rate.to_le_bytes());
+        }
+        if self.metadata {
+            bytes[18] |= FLAG_METADATA;
+        }
+        bytes
+    }
+
+    // Anything past the end of an older version's header is ignored
+    pub fn from_bytes(bytes: &[u8; HEADER_LEN]) -> Result<Header, Error> {
+        if bytes[0..4] != MAGIC {
+            return Err(Error::BadMagic);
+        }
+        let len = header_len(bytes[4]).ok_or(Error::UnsupportedVersion(bytes[4]))?;
+        let mut bytes = *bytes;
+        bytes[len..].fill(0);
+
+        let pixel_format = match bytes[5] {
+            0 => PixelFormat::Gray8,
+            other => return Err(Error::UnknownPixelFormat(other)),
+        };
+        let codec = match bytes[6] {
+            0 => Codec::Rle,
+            1 => Codec::RleRoi,
+            2 => Codec::RleDelta,
+            other => return Err(Error::UnknownCodec(other)),
+        };
+        if bytes[18] & !KNOWN_FLAGS != 0 {
+            return Err(Error::UnknownFlags(bytes[18]));
+        }
+        let audio = if bytes[18] & FLAG_AUDIO != 0 {
+            let codec = match bytes[19] {
+                0 => AudioCodec::Pcm8,
+                other => return Err(Error::UnknownAudioCodec(other)),
+            };
+            Some(Audio { codec, sample_rate: u16::from_le_bytes([bytes[20], bytes[21]]) })
+        } else {
+            None
+        };
+
+        Ok(Header {
+            width: u16::from_le_bytes([bytes[8], bytes[9]]),
+            height: u16::from_le_bytes([bytes[10], bytes[11]]),
+            fps: bytes[7],
+            frame_count: u32::from_le_bytes([bytes[12], bytes[13], bytes[14], bytes[15]]),
+            pixel_format,
+            codec,
+            keyframe_interval: u16::from_le_bytes([bytes[16], bytes[17]]).max(1),
+            run_format: if bytes[18] & FLAG_VARINT_RUNS != 0 { RunFormat::Varint } else { RunFormat::Classic },
+            frame_crc: bytes[18] & FLAG_FRAME_CRC != 0,
+            audio,
+            metadata: bytes[18] & FLAG_METADATA != 0,
+        })
+    }
+
+    // Splits the metadata section off what follows the header
+    fn split_metadata<'a>(&self, data: &'a [u8]) -> Result<(Option<Metadata<'a>>, &'a [u8]), Error> {
+        if !self.metadata {
+            return Ok((None, data));
+        }
+
+        let [low, high, rest @ ..] = data else { return Err(Error::Truncated) };
+        let len = u16::from_le_bytes([*low, *high]) as usize;
+        if len > rest.len() {
+            return Err(Error::Truncated);
+        }
+        let (section, rest) = rest.split_at(len);
+        Ok((Some(Metadata(section)), rest))
+    }
+
+    // The metadata section from what follows the header, if the file has one
+    pub fn metadata<'a>(&self, data: &'a [u8]) -> Result<Option<Metadata<'a>>, Error> {
+        Ok(self.split_metadata(data)?.0)
+    }
+
+    // Splits what follows the header into the encoded frames and the CRC table, if the file has one.
+    // The metadata section is skipped.
+    pub fn split_frames<'a>(&self, data: &'a [u8]) -> Result<(&'a [u8], Option<FrameCrcs<'a>>), Error> {
+        let (_, data) = self.split_metadata(data)?;
+        if !self.frame_crc {
+            return Ok((data, None));
+        }
+
+        let table_len = (self.frame_count as usize).checked_mul(4).ok_or(Error::Truncated)?;
+        let frames_len = data.len().checked_sub(table_len).ok_or(Error::Truncated)?;
+        let (frames, table) = data.split_at(frames_len);
+        Ok((frames, Some(FrameCrcs(table))))
+    }
+
+    // The video half of the frames from split_frames, as one stream for rle::Decoder
+    pub fn video_track<'a>(&self, frames: &'a [u8]) -> VideoTrack<'a> {
+        match self.audio {
+            None => VideoTrack { chunks: None, chunk: frames },
+            Some(_) => VideoTrack { chunks: Some(Demuxer::new(frames)), chunk: &[] },
+        }
+    }
+
+    // Decodes every frame in what follows the header and checks it against the CRC table, eg. to catch
+    // flash corruption before playing. Files without a CRC table pass. `buf` has to hold one frame, any
+    // extra space is left alone.
+    pub fn verify_frames(&self, data: &[u8], buf: &mut [u8]) -> Result<(), VerifyError> {
+        let (frames, crcs) = self.split_frames(data).map_err(VerifyError::Container)?;
+        let Some(crcs) = crcs else { return Ok(()) };
+        let buf = buf.get_mut(..self.frame_len()).ok_or(VerifyError::BufferTooSmall)?;
+        let mut decoder = rle::Decoder::new(self.video_track(frames)).with_format(self.run_format);
+
+        if self.codec == Codec::RleRoi {
+            for frame in 0..self.frame_count {
+                let Ok(Some(region)) = roi::read_frame(&mut decoder, buf) else { return Err(VerifyError::Corrupt(frame)) };
+                if !crcs.check(frame as usize, &buf[..region.area()]) {
+                    return Err(VerifyError::Mismatch(frame));
+                }
+            }
+        } else {
+            let interval = if self.codec == Codec::RleDelta { self.keyframe_interval as usize } else { 1 };
+            let mut decoder = rle::DeltaDecoder::new(decoder, buf, interval);
+            for frame in 0..self.frame_count {
+                if decoder.read_frame() != Ok(true) {
+                    return Err(VerifyError::Corrupt(frame));
+                }
+                if !crcs.check(frame as usize, decoder.frame()) {
+                    return Err(VerifyError::Mismatch(frame));
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn write<W: Write>(&self, writer: &mut W) -> Result<(), W::Error> {
+        writer.write_all(&self.to_bytes())
+    }
+
+    // Leaves the reader at the start of the encoded frames, also for older, shorter headers
+    pub fn read<R: Read>(reader: &mut R) -> Result<Header, Error<R::Error>> {
+        let map_err = |err| match err {
+            ReadExactError::UnexpectedEof => Error::Truncated,
+            ReadExactError::Other(err) => Error::Read(err),
+        };
+
+        let mut bytes = [0; HEADER_LEN];
+        reader.read_exact(&mut bytes[..5]).map_err(map_err)?;
+        if bytes[0..4] != MAGIC {
+            return Err(Error::BadMagic);
+        }
+        let len = header_len(bytes[4]).ok_or(Error::UnsupportedVersion(bytes[4]))?;
+        reader.read_exact(&mut bytes[5..len]).map_err(map_err)?;
+
+        Header::from_bytes(&bytes).map_err(Error::widen)
+    }
+}
+
+impl<'a> Metadata<'a> {
+    pub fn get(&self, tag: u8) -> Option<&'a [u8]> {
+        self.entries().find(|entry| entry.tag == tag).map(|entry| entry.value)
+    }
+
+    pub fn title(&self) -> Option<&'a str> {
+        self.get(TAG_TITLE).and_then(|value| core::str::from_utf8(value).ok())
+    }
+
+    pub fn author(&self) -> Option<&'a str> {
+        self.get(TAG_AUTHOR).and_then(|value| core::str::from_utf8(value).ok())
+    }
+
+    pub fn duration_ms(&self) -> Option<u32> {
+        let value = self.get(TAG_DURATION)?;
+        Some(u32::from_le_bytes(value.try_into().ok()?))
+    }
+
+    // Stops at an entry that runs past the end of the section
+    pub fn entries(&self) -> impl Iterator<Item = Entry<'a>> + use<'a> {
+        let mut data = self.0;
+        core::iter::from_fn(move || {
+            let [tag, len, rest @ ..] = data else { return None };
+            let value = rest.get(..*len as usize)?;
+            data = &rest[*len as usize..];
+            Some(Entry { tag: *tag, value })
+        })
+    }
+}
+
+// Writes a metadata section. Values are up to 255 bytes and the whole section up to 65535, anything
+// longer is rejected before a byte is written.
+pub fn write_metadata<W: Write>(writer: &mut W, entries: &[Entry]) -> Result<(), WriteError<W::Error>> {
+    if let Some(entry) = entries.iter().find(|entry| entry.value.len() > u8::MAX as usize) {
+        return Err(WriteError::TooLong(entry.value.len()));
+    }
+    let len: usize = entries.iter().map(|entry| 2 + entry.value.len()).sum();
+    let len = u16::try_from(len).map_err(|_| WriteError::TooLong(len))?;
+
+    writer.write_all(&len.to_le_bytes()).map_err(WriteError::Write)?;
+    for entry in entries {
+        writer.write_all(&[entry.tag, entry.value.len() as u8]).map_err(WriteError::Write)?;
+        writer.write_all(entry.value).map_err(WriteError::Write)?;
+    }
+    Ok(())
+}
+
+impl FrameCrcs<'_> {
+    pub fn get(&self, frame: usize) -> Option<u32> {
+        let bytes = self.0.get(frame * 4..frame * 4 + 4)?;
+        Some(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    pub fn check(&self, frame: usize, pixels: &[u8]) -> bool {
+        self.get(frame) == Some(crc32(pixels))
+    }
+}
+
+// One interleaved frame
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Chunk<'a> {
+    pub video: &'a [u8],
+    pub audio: &'a [u8],
+}
+
+// Walks interleaved frames one chunk at a time, eg. to hand the audio to I2S as the video plays
+#[derive(Debug, Clone)]
+pub struct Demuxer<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Demuxer<'a> {
+    pub fn new(frames: &'a [u8]) -> Demuxer<'a> {
+        Demuxer { data: frames }
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8], Error> {
+        if len > self.data.len() {
+            self.data = &[];
+            return Err(Error::TruncatedChunk);
+        }
+
+        let (taken, rest) = self.data.split_at(len);
+        self.data = rest;
+        Ok(taken)
+    }
+
+    fn next_chunk(&mut self) -> Result<Chunk<'a>, Error> {
+        let video_len = self.take(4)?;
+        let video = self.take(u32::from_le_bytes([video_len[0], video_len[1], video_len[2], video_len[3]]) as usize)?;
+        let audio_len = self.take(2)?;
+        let audio = self.take(u16::from_le_bytes([audio_len[0], audio_len[1]]) as usize)?;
+
+        Ok(Chunk { video, audio })
+    }
+}
+
+impl<'a> Iterator for Demuxer<'a> {
+    type Item = Result<Chunk<'a>, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.data.is_empty() {
+            return None;
+        }
+
+        Some(self.next_chunk())
+    }
+}
+
+// Writes one interleaved frame, audio chunks are up to 65535 samples
+pub fn write_chunk<W: Write>(writer: &mut W, chunk: Chunk) -> Result<(), WriteError<W::Error>> {
+    let video_len = u32::try_from(chunk.video.len()).map_err(|_| WriteError::TooLong(chunk.video.len()))?;
+    let audio_len = u16::try_from(chunk.audio.len()).map_err(|_| WriteError::TooLong(chunk.audio.len()))?;
+
+    writer.write_all(&video_len.to_le_bytes()).map_err(WriteError::Write)?;
+    writer.write_all(chunk.video).map_err(WriteError::Write)?;
+    writer.write_all(&audio_len.to_le_bytes()).map_err(WriteError::Write)?;
+    writer.write_all(chunk.audio).map_err(WriteError::Write)
+}
+
+// Reads the video chunks back to back, skipping the audio. Without an audio track it reads the frames as is.
+pub struct VideoTrack<'a> {
+    chunks: Option<Demuxer<'a>>,
+    chunk: &'a [u8],
+}
+
+impl ErrorType for VideoTrack<'_> {
+    type Error = Error;
+}
+
+impl Read for VideoTrack<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, Error> {
+        while self.chunk.is_empty() {
+            match self.chunks.as_mut().and_then(Iterator::next) {
+                None => return Ok(0),
+                Some(chunk) => self.chunk = chunk?.video,
+            }
+        }
+
+        self.chunk.read(buf).map_err(|never| match never {})
+    }
+}
+
+impl fmt::Display for VerifyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VerifyError::Container(err) => write!(f, "{err}"),
+            VerifyError::Corrupt(frame) => write!(f, "Frame {frame} failed to decode"),
+            VerifyError::Mismatch(frame) => write!(f, "Frame {frame} does not match its CRC"),
+            VerifyError::BufferTooSmall => write!(f, "Buffer is too small for a frame"),
+        }
+    }
+}
+
+impl core::error::Error for VerifyError {}
+
+impl<E: fmt::Debug> fmt::Display for WriteError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WriteError::Write(err) => write!(f, "Failed to write .smol data: {err:?}"),
+            WriteError::TooLong(len) => write!(f, "{len} bytes don't fit their .smol length field"),
+        }
+    }
+}
+
+impl<E: fmt::Debug> core::error::Error for WriteError<E> {}
+
+impl Error {
+    fn widen<E>(self) -> Error<E> {
+        match self {
+            Error::Read(never) => match never {},
+            Error::Truncated => Error::Truncated,
+            Error::BadMagic => Error::BadMagic,
+            Error::UnsupportedVersion(version) => Error::UnsupportedVersion(version),
+            Error::UnknownPixelFormat(format) => Error::UnknownPixelFormat(format),
+            Error::UnknownCodec(codec) => Error::UnknownCodec(codec),
+            Error::UnknownFlags(flags) => Error::UnknownFlags(flags),
+            Error::UnknownAudioCodec(codec) => Error::UnknownAudioCodec(codec),
+            Error::TruncatedChunk => Error::TruncatedChunk,
+        }
+    }
+}
+
+impl<E: fmt::Debug> fmt::Display for Error<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Read(err) => write!(f, "Failed to read .smol header: {err:?}"),
+            Error::Truncated => write!(f, "Truncated .smol header"),
+            Error::BadMagic => write!(f, "Not a .smol file"),
+            Error::UnsupportedVersion(version) => write!(f, "Unsupported .smol version {version}"),
+            Error::UnknownPixelFormat(format) => write!(f, "Unknown pixel format {format}"),
+            Error::UnknownCodec(codec) => write!(f, "Unknown codec {codec}"),
+            Error::UnknownFlags(flags) => write!(f, "Unknown .smol flags {flags:#04x}"),
+            Error::UnknownAudioCodec(codec) => write!(f, "Unknown audio codec {codec}"),
+            Error::TruncatedChunk => write!(f, "Truncated .smol frame chunk"),
+        }
+    }
+}
+
+impl<E: fmt::Debug> core::error::Error for Error<E> {}
+
+impl embedded_io::Error for Error {
+    fn kind(&self) -> ErrorKind {
+        ErrorKind::InvalidData
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::vec::Vec;
+    use crate::rle;
+
+    #[test]
+    fn test_header() {
+        let raw = include_bytes!("../../assets/XD.raw");
+        let header = Header::new(160, 128, 30, 37).with_codec(Codec::Rle);
+        assert_eq!(header.frame_len() * header.frame_count as usize, raw.len());
+
+        let mut file = Vec::new();
+        header.write(&mut file).unwrap();
+        let mut encoder = rle::Encoder::new(file);
+        embedded_io::Write::write_all(&mut encoder, raw).unwrap();
+        let file = encoder.finalize().unwrap();
+
+        let mut data = &file[..];
+        assert_eq!(Header::read(&mut data), Ok(header));
+        let mut frame = std::vec![0; header.frame_len()];
+        rle::Decoder::new(data).read_exact(&mut frame).unwrap();
+        assert_eq!(&frame[..], &raw[..header.frame_len()]);
+
+        let mut bytes = header.to_bytes();
+        bytes[6] = 9;
+        assert_eq!(Header::from_bytes(&bytes), Err(Error::UnknownCodec(9)));
+        bytes[4] = VERSION + 1;
+        assert_eq!(Header::from_bytes(&bytes), Err(Error::UnsupportedVersion(VERSION + 1)));
+        bytes[4] = 0;
+        assert_eq!(Header::read(&mut &bytes[..]), Err(Error::UnsupportedVersion(0)));
+        assert_eq!(Header::from_bytes(&[0; HEADER_LEN]), Err(Error::BadMagic));
+        assert_eq!(Header::read(&mut &file[..10]), Err(Error::Truncated));
+
+        let delta = header.with_codec(Codec::RleDelta).with_keyframe_interval(30).with_run_format(RunFormat::Varint);
+        assert_eq!(Header::from_bytes(&delta.to_bytes()), Ok(delta));
+
+        // Older versions have shorter headers, the frames start right after them
+        let mut v1 = header.to_bytes()[..16].to_vec();
+        v1[4] = 1;
+        v1.extend_from_slice(&[0x84, 7, 0xFF, 0xFF, 0xFF, 0xFF]);
+        let mut data = &v1[..];
+        assert_eq!(Header::read(&mut data), Ok(header));
+        assert_eq!(data, [0x84, 7, 0xFF, 0xFF, 0xFF, 0xFF]);
+        assert_eq!(Header::from_bytes(v1[..HEADER_LEN].try_into().unwrap()), Ok(header));
+        let mut v3 = delta.to_bytes()[..19].to_vec();
+        v3[4] = 3;
+        assert_eq!(Header::read(&mut &v3[..]), Ok(delta));
+        assert_eq!(Header::read(&mut &v3[..18]), Err(Error::Truncated));
+        let mut bytes = header.to_bytes();
+        bytes[18] = 0x84;
+        assert_eq!(Header::from_bytes(&bytes), Err(Error::UnknownFlags(0x84)));
+
+        // CRC table after the frames, one flipped byte is caught
+        let header = header.with_frame_crc(true);
+        let mut file = header.to_bytes().to_vec();
+        let mut encoder = rle::Encoder::new(file);
+        embedded_io::Write::write_all(&mut encoder, raw).unwrap();
+        file = encoder.finalize().unwrap();
+        for frame in raw.chunks_exact(header.frame_len()) {
+            file.extend_from_slice(&crate::crc32::crc32(frame).to_le_bytes());
+        }
+
+        let mut data = &file[..];
+        assert_eq!(Header::read(&mut data), Ok(header));
+        let (frames, _) = header.split_frames(data).unwrap();
+        let mut buf = std::vec![0; header.frame_len()];
+        assert_eq!(header.verify_frames(data, &mut buf), Ok(()));
+        let mut big = std::vec![0xAA; header.frame_len() + 16];
+        assert_eq!(header.verify_frames(data, &mut big), Ok(()));
+        assert_eq!(big[header.frame_len()..], [0xAA; 16]);
+        assert_eq!(header.verify_frames(data, &mut buf[1..]), Err(VerifyError::BufferTooSmall));
+
+        let mut corrupt = data.to_vec();
+        corrupt[frames.len() - 1] ^= 0x55;
+        assert_eq!(header.verify_frames(&corrupt, &mut buf), Err(VerifyError::Mismatch(36)));
+        assert_eq!(header.verify_frames(&data[..100], &mut buf), Err(VerifyError::Container(Error::Truncated)));
+        assert_eq!(header.split_frames(&data[..100]), Err(Error::Truncated));
+
+        // Interleaved audio, every frame's runs encoded on their own
+        let header = Header::new(160, 128, 30, 37).with_audio(Audio { codec: AudioCodec::Pcm8, sample_rate: 8000 });
+        assert_eq!(Header::from_bytes(&header.to_bytes()), Ok(header));
+        let mut file = Vec::new();
+        for (i, frame) in raw.chunks_exact(header.frame_len()).enumerate() {
+            let mut encoder = rle::Encoder::new(Vec::new());
+            embedded_io::Write::write_all(&mut encoder, frame).unwrap();
+            let audio = [i as u8; 266];
+            write_chunk(&mut file, Chunk { video: &encoder.finalize().unwrap(), audio: &audio }).unwrap();
+        }
+
+        let mut demuxer = Demuxer::new(&file);
+        assert_eq!(demuxer.nth(5).unwrap().unwrap().audio, [5; 266]);
+        assert_eq!(demuxer.count(), 31);
+        let mut video = std::vec![0; raw.len()];
+        rle::Decoder::new(header.video_track(&file)).read_exact(&mut video).unwrap();
+        assert_eq!(&video[..], &raw[..]);
+        assert_eq!(Demuxer::new(&file[..file.len() - 1]).last(), Some(Err(Error::TruncatedChunk)));
+        let long = std::vec![0; 65536];
+        assert_eq!(write_chunk(&mut Vec::new(), Chunk { video: &[], audio: &long }), Err(WriteError::TooLong(65536)));
+
+        // Metadata between the header and the frames, skipped by split_frames
+        let header = Header::new(160, 128, 30, 37).with_metadata(true);
+        let mut file = Vec::new();
+        let duration = 1233u32.to_le_bytes();
+        write_metadata(&mut file, &[
+            Entry { tag: TAG_TITLE, value: b"XD" },
+            Entry { tag: 99, value: &[1, 2, 3] },
+            Entry { tag: TAG_DURATION, value: &duration },
+        ]).unwrap();
+        file.extend_from_slice(&[0x84, 7]);
+
+        let metadata = header.metadata(&file).unwrap().unwrap();
+        assert_eq!((metadata.title(), metadata.author(), metadata.duration_ms()), (Some("XD"), None, Some(1233)));
+        assert_eq!(metadata.get(99), Some(&[1, 2, 3][..]));
+        assert_eq!(header.split_frames(&file), Ok((&[0x84, 7][..], None)));
+        assert_eq!(header.split_frames(&file[..5]), Err(Error::Truncated));
+
+        let long = [b'x'; 256];
+        assert_eq!(write_metadata(&mut Vec::new(), &[Entry { tag: TAG_TITLE, value: &long }]), Err(WriteError::TooLong(256)));
+        let entries = [Entry { tag: TAG_TITLE, value: &long[..255] }; 256];
+        assert_eq!(write_metadata(&mut Vec::new(), &entries), Err(WriteError::TooLong(256 * 257)));
+    }
+}
@@ -0,0 +1,152 @@
+use core::time::Duration;
+
+
+pub struct Stopwatch<const LAPS: usize> {
+    started_at: Option<Duration>,
+    accumulated: Duration,
+    laps: [Duration; LAPS],
+    lap_count: usize,
+    last_lap: Duration,
+    split: Option<Duration>,
+    splits_reached: u32,
+}
+
+impl<const LAPS: usize> Stopwatch<LAPS> {
+    pub fn new() -> Self {
+        Stopwatch {
+            started_at: None,
+            accumulated: Duration::ZERO,
+            laps: [Duration::ZERO; LAPS],
+            lap_count: 0,
+            last_lap: Duration::ZERO,
+            split: None,
+            splits_reached: 0,
+        }
+    }
+
+    pub fn with_split(mut self, split: Duration) -> Self {
+        self.split = Some(split).filter(|split| !split.is_zero());
+        self
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.started_at.is_some()
+    }
+
+    pub fn start(&mut self, now: Duration) {
+        if self.started_at.is_none() {
+            self.started_at = Some(now);
+        }
+    }
+
+    pub fn stop(&mut self, now: Duration) {
+        if let Some(started_at) = self.started_at.take() {
+            self.accumulated += now.saturating_sub(started_at);
+        }
+    }
+
+    pub fn toggle(&mut self, now: Duration) {
+        if self.is_running() {
+            self.stop(now);
+        } else {
+            self.start(now);
+        }
+    }
+
+    pub fn reset(&mut self) {
+        *self = Stopwatch {
+            split: self.split,
+            ..Stopwatch::new()
+        };
+    }
+
+    pub fn elapsed(&self, now: Duration) -> Duration {
+        match self.started_at {
+            Some(started_at) => self.accumulated + now.saturating_sub(started_at),
+            None => self.accumulated,
+        }
+    }
+
+    // Returns the lap time, or None when the lap table is full
+    pub fn lap(&mut self, now: Duration) -> Option<Duration> {
+        if self.lap_count >= LAPS {
+            return None;
+        }
+
+        // A stale `now` makes an empty lap instead of going back in time
+        let total = self.elapsed(now).max(self.last_lap);
+        let lap = total.saturating_sub(self.last_lap);
+        self.laps[self.lap_count] = lap;
+        self.lap_count += 1;
+        self.last_lap = total;
+
+        Some(lap)
+    }
+
+    pub fn laps(&self) -> &[Duration] {
+        &self.laps[..self.lap_count]
+    }
+
+    // True once every time another split interval has elapsed, meant for driving the buzzer
+    pub fn split_reached(&mut self, now: Duration) -> bool {
+        let Some(split) = self.split else { return false };
+        let splits = (self.elapsed(now).as_millis() / split.as_millis().max(1)) as u32;
+
+        if splits > self.splits_reached {
+            self.splits_reached = splits;
+            true
+        } else {
+            false
+        }
+    }
+}
+
+impl<const LAPS: usize> Default for Stopwatch<LAPS> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ms(ms: u64) -> Duration {
+        Duration::from_millis(ms)
+    }
+
+    #[test]
+    fn test_stopwatch() {
+        let mut sw = Stopwatch::<2>::new().with_split(ms(1000));
+
+        assert_eq!(sw.elapsed(ms(500)), ms(0));
+        assert!(!sw.split_reached(ms(500)));
+
+        sw.start(ms(1000));
+        assert_eq!(sw.elapsed(ms(1250)), ms(250));
+        assert_eq!(sw.lap(ms(1300)), Some(ms(300)));
+
+        sw.stop(ms(1500));
+        assert_eq!(sw.elapsed(ms(9000)), ms(500));
+
+        sw.toggle(ms(10000));
+        assert!(sw.is_running());
+        assert!(!sw.split_reached(ms(10400)));
+        assert!(sw.split_reached(ms(10600)));
+        assert!(!sw.split_reached(ms(10700)));
+
+        assert_eq!(sw.lap(ms(10800)), Some(ms(1000)));
+        assert_eq!(sw.lap(ms(10900)), None);
+        assert_eq!(sw.laps(), &[ms(300), ms(1000)]);
+
+        sw.reset();
+        assert!(!sw.is_running());
+        assert_eq!(sw.laps(), &[]);
+        assert_eq!(sw.elapsed(ms(20000)), ms(0));
+
+        sw.start(ms(20000));
+        assert_eq!(sw.lap(ms(20500)), Some(ms(500)));
+        assert_eq!(sw.lap(ms(20100)), Some(ms(0)));
+    }
+}
@@ -0,0 +1,197 @@
+use embedded_io::{Read, Write};
+use crate::{crc32, rle, roi, smol};
+
+
+// Canonical encoded/decoded pairs for every codec and the container header. Host tests and the
+// device self-test run the same `check`, so a miscompile or endianness bug on the target shows up
+// as a named failing vector instead of a garbled video.
+pub struct Vector {
+    pub name: &'static str,
+    pub decoded: &'static [u8],
+    pub encoded: &'static [u8],
+}
+
+const fn counting<const N: usize>() -> [u8; N] {
+    let mut bytes = [0; N];
+    let mut i = 0;
+    while i < N {
+        bytes[i] = i as u8;
+        i += 1;
+    }
+    bytes
+}
+
+// 140 distinct bytes don't fit in one literal, the encoder splits them 129 + 11
+const LONG_LITERAL: [u8; 140] = counting();
+const LONG_LITERAL_ENCODED: [u8; 142] = {
+    let mut bytes = [0; 142];
+    bytes[0] = 127;
+    bytes[130] = 9;
+    let mut i = 0;
+    while i < 140 {
+        bytes[if i < 129 { i + 1 } else { i + 2 }] = i as u8;
+        i += 1;
+    }
+    bytes
+};
+
+pub const RLE: [Vector; 5] = [
+    Vector { name: "rle repeat", decoded: &[7; 5], encoded: &[0x84, 7] },
+    Vector { name: "rle literal", decoded: &[1, 2, 3], encoded: &[0x01, 1, 2, 3] },
+    Vector { name: "rle mixed", decoded: &[1, 1, 1, 2, 3, 4, 4], encoded: &[0x82, 1, 0x00, 2, 3, 0x81, 4] },
+    Vector { name: "rle long repeat", decoded: &[0; 200], encoded: &[0xFF, 0, 0xC7, 0] },
+    Vector { name: "rle long literal", decoded: &LONG_LITERAL, encoded: &LONG_LITERAL_ENCODED },
+];
+
+// 200 zeros in one varint run: (199 << 1 | 1) = 399
+pub const VARINT: Vector = Vector {
+    name: "rle varint",
+    decoded: &[0; 200],
+    encoded: &[0x8F, 0x03, 0],
+};
+
+// 4x2 frame over a black one, only the 2x1 region at (1, 1) changed
+pub const ROI: Vector = Vector {
+    name: "roi frame",
+    decoded: &[0, 0, 0, 0, 0, 9, 9, 0],
+    encoded: &[0x06, 1, 0, 1, 0, 2, 0, 1, 0, 0x81, 9],
+};
+
+// Three 4 byte frames with a keyframe interval of 2, the middle one is XORed with the first
+pub const DELTA: Vector = Vector {
+    name: "delta frames",
+    decoded: &[5, 5, 5, 5, 5, 5, 6, 5, 1, 2, 3, 4],
+    encoded: &[0x83, 5, 0x81, 0, 0x04, 3, 0, 1, 2, 3, 4],
+};
+
+// Header::new(160, 128, 30, 37), all multi-byte fields little-endian
+pub const HEADER: Vector = Vector {
+    name: "smol header",
+    decoded: &[],
+    encoded: &[b'S', b'M', b'O', b'L', 4, 0, 0, 30, 160, 0, 128, 0, 37, 0, 0, 0, 1, 0, 0, 0, 0, 0],
+};
+
+// Check value from the CRC-32 catalogue, stored little-endian like the .smol CRC table
+pub const CRC32: Vector = Vector {
+    name: "crc32",
+    decoded: b"123456789",
+    encoded: &[0x26, 0x39, 0xF4, 0xCB],
+};
+
+fn check_rle(vector: &Vector) -> bool {
+    let mut encoded = [0; 160];
+    let mut decoded = [0; 256];
+
+    let Ok(len) = rle::encode_to_slice(vector.decoded, &mut encoded) else { return false };
+    let decoded_len = rle::decode_to_slice(vector.encoded, &mut decoded);
+
+    encoded[..len] == *vector.encoded && decoded[..decoded_len] == *vector.decoded
+}
+
+fn check_varint() -> bool {
+    let mut encoded = [0; 8];
+    let mut encoder = rle::Encoder::new(&mut encoded[..]).with_format(rle::RunFormat::Varint);
+    if encoder.write_all(VARINT.decoded).is_err() {
+        return false;
+    }
+    let Ok(rest) = encoder.finalize() else { return false };
+    let len = 8 - rest.len();
+
+    let mut decoded = [0; 256];
+    let mut decoder = rle::Decoder::new(VARINT.encoded).with_format(rle::RunFormat::Varint);
+    let mut decoded_len = 0;
+    while let Ok(read @ 1..) = decoder.read(&mut decoded[decoded_len..]) {
+        decoded_len += read;
+    }
+
+    encoded[..len] == *VARINT.encoded && decoded[..decoded_len] == *VARINT.decoded
+}
+
+fn check_roi() -> bool {
+    let mut encoded = [0; 32];
+    let mut encoder = rle::Encoder::new(&mut encoded[..]);
+    let region = roi::diff(&[0; 8], ROI.decoded, 4);
+    if roi::write_frame(&mut encoder, ROI.decoded, 4, region).is_err() {
+        return false;
+    }
+    let Ok(rest) = encoder.finalize() else { return false };
+    let len = 32 - rest.len();
+
+    let mut pixels = [0; 8];
+    let Ok(Some(read)) = roi::read_frame(&mut rle::Decoder::new(ROI.encoded), &mut pixels) else { return false };
+
+    encoded[..len] == *ROI.encoded && read == region && pixels[..read.area()] == [9, 9]
+}
+
+fn check_delta() -> bool {
+    let mut encoded = [0; 32];
+    let mut prev = [0; 4];
+    let mut encoder = rle::DeltaEncoder::new(rle::Encoder::new(&mut encoded[..]), &mut prev, 2);
+    for frame in DELTA.decoded.chunks_exact(4) {
+        if encoder.write_frame(frame).is_err() {
+            return false;
+        }
+    }
+    let Ok(rest) = encoder.finalize() else { return false };
+    let len = 32 - rest.len();
+
+    let mut frame_buf = [0; 4];
+    let mut decoder = rle::DeltaDecoder::new(rle::Decoder::new(DELTA.encoded), &mut frame_buf, 2);
+    for frame in DELTA.decoded.chunks_exact(4) {
+        if decoder.read_frame() != Ok(true) || decoder.frame() != frame {
+            return false;
+        }
+    }
+
+    encoded[..len] == *DELTA.encoded && decoder.read_frame() == Ok(false)
+}
+
+fn check_header() -> bool {
+    let header = smol::Header::new(160, 128, 30, 37);
+    let mut encoded = [0; smol::HEADER_LEN];
+    if header.write(&mut &mut encoded[..]).is_err() {
+        return false;
+    }
+
+    let mut input = HEADER.encoded;
+    encoded == *HEADER.encoded && smol::Header::read(&mut input) == Ok(header)
+}
+
+// Runs every vector, returns the name of the first one that fails
+pub fn check() -> Result<(), &'static str> {
+    for vector in &RLE {
+        if !check_rle(vector) {
+            return Err(vector.name);
+        }
+    }
+
+    if !check_varint() {
+        return Err(VARINT.name);
+    }
+    if !check_roi() {
+        return Err(ROI.name);
+    }
+    if !check_delta() {
+        return Err(DELTA.name);
+    }
+    if !check_header() {
+        return Err(HEADER.name);
+    }
+
+    if crc32::crc32(CRC32.decoded).to_le_bytes() != *CRC32.encoded {
+        return Err(CRC32.name);
+    }
+
+    Ok(())
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_vectors() {
+        assert_eq!(check(), Ok(()));
+    }
+}
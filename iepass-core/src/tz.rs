@@ -0,0 +1,346 @@
+// Timezones as POSIX TZ strings, eg. "CET-1CEST,M3.5.0,M10.5.0/3"
+
+pub const ZONES: &[(&str, &str)] = &[
+    ("UTC", "UTC0"),
+    ("Europe/London", "GMT0BST,M3.5.0/1,M10.5.0"),
+    ("Europe/Warsaw", "CET-1CEST,M3.5.0,M10.5.0/3"),
+    ("Europe/Berlin", "CET-1CEST,M3.5.0,M10.5.0/3"),
+    ("Europe/Helsinki", "EET-2EEST,M3.5.0/3,M10.5.0/4"),
+    ("Europe/Moscow", "MSK-3"),
+    ("America/New_York", "EST5EDT,M3.2.0,M11.1.0"),
+    ("America/Chicago", "CST6CDT,M3.2.0,M11.1.0"),
+    ("America/Denver", "MST7MDT,M3.2.0,M11.1.0"),
+    ("America/Los_Angeles", "PST8PDT,M3.2.0,M11.1.0"),
+    ("America/Sao_Paulo", "<-03>3"),
+    ("Asia/Kolkata", "IST-5:30"),
+    ("Asia/Shanghai", "CST-8"),
+    ("Asia/Tokyo", "JST-9"),
+    ("Australia/Sydney", "AEST-10AEDT,M10.1.0,M4.1.0/3"),
+    ("Pacific/Auckland", "NZST-12NZDT,M9.5.0,M4.1.0/3"),
+];
+
+pub fn lookup(name: &str) -> Option<&'static str> {
+    ZONES.iter().find(|(zone, _)| *zone == name).map(|&(_, tz)| tz)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParseTzError;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Date {
+    // Month 1-12, week 1-5 (5 = last), weekday 0-6 (0 = Sunday)
+    Weekday { month: u8, week: u8, weekday: u8 },
+    // Day of year 1-365, February 29th is never counted
+    Julian(u16),
+    // Day of year 0-365, leap days counted
+    Day(u16),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Transition {
+    pub date: Date,
+    // Seconds after local midnight
+    pub time: i32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Dst<'a> {
+    pub name: &'a str,
+    pub offset: i32,
+    pub start: Transition,
+    pub end: Transition,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimeZone<'a> {
+    pub name: &'a str,
+    // Seconds east of UTC, the opposite sign of what POSIX TZ strings use
+    pub offset: i32,
+    pub dst: Option<Dst<'a>>,
+}
+
+struct Parser<'a> {
+    input: &'a str,
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<u8> {
+        self.input.as_bytes().first().copied()
+    }
+
+    fn eat(&mut self, byte: u8) -> bool {
+        if self.peek() == Some(byte) {
+            self.input = &self.input[1..];
+            true
+        } else {
+            false
+        }
+    }
+
+    fn name(&mut self) -> Result<&'a str, ParseTzError> {
+        let name = if self.eat(b'<') {
+            let end = self.input.find('>').ok_or(ParseTzError)?;
+            let name = &self.input[..end];
+            self.input = &self.input[end + 1..];
+            name
+        } else {
+            let end = self.input.find(|c: char| !c.is_ascii_alphabetic()).unwrap_or(self.input.len());
+            let name = &self.input[..end];
+            self.input = &self.input[end..];
+            name
+        };
+
+        if name.len() < 3 { Err(ParseTzError) } else { Ok(name) }
+    }
+
+    fn number(&mut self) -> Result<i32, ParseTzError> {
+        let end = self.input.find(|c: char| !c.is_ascii_digit()).unwrap_or(self.input.len());
+        let number = self.input[..end].parse().map_err(|_| ParseTzError)?;
+        self.input = &self.input[end..];
+        Ok(number)
+    }
+
+    // [+-]hh[:mm[:ss]] in seconds
+    fn time(&mut self) -> Result<i32, ParseTzError> {
+        let sign = if self.eat(b'-') { -1 } else { self.eat(b'+'); 1 };
+        // POSIX allows hours up to 167, which keeps the sum well within i32
+        let hours = self.number()?;
+        if hours > 167 { return Err(ParseTzError) }
+        let mut seconds = hours * 3600;
+
+        if self.eat(b':') {
+            match self.number()? {
+                minutes @ 0..=59 => seconds += minutes * 60,
+                _ => return Err(ParseTzError),
+            }
+            if self.eat(b':') {
+                match self.number()? {
+                    secs @ 0..=59 => seconds += secs,
+                    _ => return Err(ParseTzError),
+                }
+            }
+        }
+
+        Ok(sign * seconds)
+    }
+
+    fn transition(&mut self) -> Result<Transition, ParseTzError> {
+        if !self.eat(b',') {
+            return Err(ParseTzError);
+        }
+
+        let date = if self.eat(b'M') {
+            let month = self.number()?;
+            if !self.eat(b'.') { return Err(ParseTzError) }
+            let week = self.number()?;
+            if !self.eat(b'.') { return Err(ParseTzError) }
+            let weekday = self.number()?;
+
+            if !(1..=12).contains(&month) || !(1..=5).contains(&week) || !(0..=6).contains(&weekday) {
+                return Err(ParseTzError);
+            }
+            Date::Weekday { month: month as u8, week: week as u8, weekday: weekday as u8 }
+        } else if self.eat(b'J') {
+            match self.number()? {
+                day @ 1..=365 => Date::Julian(day as u16),
+                _ => return Err(ParseTzError),
+            }
+        } else {
+            match self.number()? {
+                day @ 0..=365 => Date::Day(day as u16),
+                _ => return Err(ParseTzError),
+            }
+        };
+
+        let time = if self.eat(b'/') { self.time()? } else { 2 * 3600 };
+
+        Ok(Transition { date, time })
+    }
+}
+
+impl<'a> TimeZone<'a> {
+    pub fn parse(input: &'a str) -> Result<TimeZone<'a>, ParseTzError> {
+        let mut parser = Parser { input };
+        let name = parser.name()?;
+        let offset = -parser.time()?;
+
+        let dst = if parser.input.is_empty() {
+            None
+        } else {
+            let dst_name = parser.name()?;
+            let dst_offset = match parser.peek() {
+                Some(b'+' | b'-' | b'0'..=b'9') => -parser.time()?,
+                _ => offset + 3600,
+            };
+            // Rules default to the US ones when omitted, like glibc does
+            let (start, end) = if parser.input.is_empty() {
+                (Transition { date: Date::Weekday { month: 3, week: 2, weekday: 0 }, time: 7200 },
+                 Transition { date: Date::Weekday { month: 11, week: 1, weekday: 0 }, time: 7200 })
+            } else {
+                (parser.transition()?, parser.transition()?)
+            };
+
+            Some(Dst {
+                name: dst_name,
+                offset: dst_offset,
+                start,
+                end,
+            })
+        };
+
+        if !parser.input.is_empty() {
+            return Err(ParseTzError);
+        }
+
+        Ok(TimeZone { name, offset, dst })
+    }
+
+    pub fn is_dst(&self, utc: i64) -> bool {
+        let Some(dst) = self.dst else { return false };
+        let year = civil_from_days((utc + self.offset as i64).div_euclid(86400)).0;
+
+        // Start is given in standard local time, end in daylight local time
+        let start = transition_local(year, &dst.start) - self.offset as i64;
+        let end = transition_local(year, &dst.end) - dst.offset as i64;
+
+        if start <= end {
+            start <= utc && utc < end
+        } else {
+            utc < end || start <= utc
+        }
+    }
+
+    // Seconds east of UTC in effect at the given unix time
+    pub fn utc_offset(&self, utc: i64) -> i32 {
+        match self.dst {
+            Some(dst) if self.is_dst(utc) => dst.offset,
+            _ => self.offset,
+        }
+    }
+
+    pub fn to_local(&self, utc: i64) -> i64 {
+        utc + self.utc_offset(utc) as i64
+    }
+
+    pub fn abbreviation(&self, utc: i64) -> &'a str {
+        match self.dst {
+            Some(dst) if self.is_dst(utc) => dst.name,
+            _ => self.name,
+        }
+    }
+}
+
+fn is_leap(year: i64) -> bool {
+    year % 4 == 0 && (year % 100 != 0 || year % 400 == 0)
+}
+
+// Days since 1970-01-01, from Howard Hinnant's date algorithms
+pub fn days_from_civil(year: i64, month: u8, day: u8) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let yoe = year - era * 400;
+    let month = month as i64;
+    let doy = (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + day as i64 - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+
+    era * 146097 + doe - 719468
+}
+
+pub fn civil_from_days(days: i64) -> (i64, u8, u8) {
+    let days = days + 719468;
+    let era = days.div_euclid(146097);
+    let doe = days - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u8;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u8;
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    (year, month, day)
+}
+
+// 0 = Sunday
+pub fn weekday(days: i64) -> u8 {
+    (days + 4).rem_euclid(7) as u8
+}
+
+fn transition_local(year: i64, transition: &Transition) -> i64 {
+    let days = match transition.date {
+        Date::Weekday { month, week, weekday: target } => {
+            let first = days_from_civil(year, month, 1);
+            let first_match = first + ((target + 7 - weekday(first)) % 7) as i64;
+            let mut day = first_match + (week as i64 - 1) * 7;
+
+            let next_month = if month == 12 { days_from_civil(year + 1, 1, 1) } else { days_from_civil(year, month + 1, 1) };
+            if day >= next_month {
+                day -= 7;
+            }
+            day
+        }
+        Date::Julian(day) => {
+            let skip_leap = is_leap(year) && day >= 60;
+            days_from_civil(year, 1, 1) + day as i64 - 1 + skip_leap as i64
+        }
+        Date::Day(day) => days_from_civil(year, 1, 1) + day as i64,
+    };
+
+    days * 86400 + transition.time as i64
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn utc(year: i64, month: u8, day: u8, hour: i64, minute: i64) -> i64 {
+        days_from_civil(year, month, day) * 86400 + hour * 3600 + minute * 60
+    }
+
+    #[test]
+    fn test_tz() {
+        assert_eq!(days_from_civil(1970, 1, 1), 0);
+        assert_eq!(civil_from_days(days_from_civil(2024, 2, 29)), (2024, 2, 29));
+        assert_eq!(weekday(days_from_civil(2025, 3, 30)), 0);
+
+        for (_, tz) in ZONES {
+            assert!(TimeZone::parse(tz).is_ok(), "{tz}");
+        }
+
+        let warsaw = TimeZone::parse(lookup("Europe/Warsaw").unwrap()).unwrap();
+        assert_eq!(warsaw.offset, 3600);
+        // 2025: DST from March 30th 01:00 UTC to October 26th 01:00 UTC
+        assert_eq!(warsaw.utc_offset(utc(2025, 3, 30, 0, 59)), 3600);
+        assert_eq!(warsaw.utc_offset(utc(2025, 3, 30, 1, 0)), 7200);
+        assert_eq!(warsaw.abbreviation(utc(2025, 7, 1, 12, 0)), "CEST");
+        assert_eq!(warsaw.utc_offset(utc(2025, 10, 26, 0, 59)), 7200);
+        assert_eq!(warsaw.utc_offset(utc(2025, 10, 26, 1, 0)), 3600);
+        assert_eq!(warsaw.to_local(utc(2025, 12, 24, 23, 30)), utc(2025, 12, 25, 0, 30));
+
+        // Southern hemisphere, 2025: DST ends April 5th 16:00 UTC, starts October 4th 16:00 UTC
+        let sydney = TimeZone::parse(lookup("Australia/Sydney").unwrap()).unwrap();
+        assert_eq!(sydney.utc_offset(utc(2025, 1, 15, 0, 0)), 11 * 3600);
+        assert_eq!(sydney.utc_offset(utc(2025, 4, 5, 15, 59)), 11 * 3600);
+        assert_eq!(sydney.utc_offset(utc(2025, 4, 5, 16, 0)), 10 * 3600);
+        assert_eq!(sydney.utc_offset(utc(2025, 10, 4, 15, 59)), 10 * 3600);
+        assert_eq!(sydney.utc_offset(utc(2025, 10, 4, 16, 0)), 11 * 3600);
+
+        let india = TimeZone::parse("IST-5:30").unwrap();
+        assert_eq!(india, TimeZone { name: "IST", offset: 5 * 3600 + 1800, dst: None });
+
+        let far = TimeZone::parse("ABC-167:59:59").unwrap();
+        assert_eq!(far.offset, 167 * 3600 + 59 * 60 + 59);
+
+        let brazil = TimeZone::parse("<-03>3").unwrap();
+        assert_eq!((brazil.name, brazil.offset), ("-03", -3 * 3600));
+
+        let julian = TimeZone::parse("XST0XDT,J60/0,300").unwrap();
+        assert!(!julian.is_dst(utc(2024, 2, 29, 12, 0)));
+        assert!(julian.is_dst(utc(2024, 3, 1, 0, 0)));
+
+        for tz in ["", "X0", "CET-1CEST,M3.5.0", "CET-1CEST,M13.1.0,M10.5.0", "CET-1CEST,M3.5.0,M10.5.0/3x",
+                   "ABC9999999", "ABC168", "ABC1:60", "ABC1:00:60", "CET-1CEST,M3.5.0,M10.5.0/999999"] {
+            assert_eq!(TimeZone::parse(tz), Err(ParseTzError), "{tz}");
+        }
+    }
+}
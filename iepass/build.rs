@@ -1,6 +1,74 @@
+use std::fmt::Write;
+use std::path::Path;
+use std::process::Command;
+use std::{env, fs, io};
+use toml_edit::DocumentMut;
 
 fn main() -> Result<(), std::io::Error> {
     embuild::espidf::sysenv::output();
     
-    Ok(())
+    let git_hash = Command::new("git")
+        .args(["rev-parse", "--short", "HEAD"])
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_string())
+        .unwrap_or_else(|| "unknown".to_string());
+    
+    println!("cargo:rustc-env=IEPASS_GIT_HASH={git_hash}");
+    println!("cargo:rerun-if-changed=../.git/HEAD");
+    println!("cargo:rerun-if-changed=../.git/refs/heads");
+    
+    generate_assets()
+}
+
+// Turns assets/assets.toml into a table of include_bytes! for src/assets.rs
+fn generate_assets() -> Result<(), io::Error> {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("../assets").canonicalize()?;
+    let manifest_path = dir.join("assets.toml");
+    println!("cargo:rerun-if-changed={}", manifest_path.display());
+    
+    let manifest = fs::read_to_string(&manifest_path)?
+        .parse::<DocumentMut>()
+        .map_err(io::Error::other)?;
+    let invalid = |message: &str| io::Error::new(io::ErrorKind::InvalidData, format!("assets.toml: {message}"));
+    
+    let default = manifest.get("default").and_then(|default| default.as_str());
+    let boot = manifest.get("boot").and_then(|boot| boot.as_str());
+    let assets = manifest.get("asset")
+        .and_then(|assets| assets.as_array_of_tables())
+        .ok_or_else(|| invalid("no [[asset]] entries"))?;
+    
+    let mut code = String::from("pub static ASSETS: &[Asset] = &[\n");
+    let mut default_index = None;
+    let mut boot_index = None;
+    for (i, asset) in assets.iter().enumerate() {
+        let name = asset.get("name").and_then(|name| name.as_str()).ok_or_else(|| invalid("asset without a name"))?;
+        let file = asset.get("file").and_then(|file| file.as_str()).ok_or_else(|| invalid("asset without a file"))?;
+        let path = dir.join(file);
+        println!("cargo:rerun-if-changed={}", path.display());
+        
+        writeln!(code, "    Asset {{ name: {name:?}, data: include_bytes!({:?}) }},", path.display().to_string()).unwrap();
+        if Some(name) == default {
+            default_index = Some(i);
+        }
+        if Some(name) == boot {
+            boot_index = Some(i);
+        }
+    }
+    code.push_str("];\n");
+    
+    let default_index = match default {
+        None => 0,
+        Some(default) => default_index.ok_or_else(|| invalid(&format!("default asset {default:?} is not listed")))?,
+    };
+    writeln!(code, "pub const DEFAULT_ASSET: usize = {default_index};").unwrap();
+    
+    let boot_index = match boot {
+        None => None,
+        Some(boot) => Some(boot_index.ok_or_else(|| invalid(&format!("boot asset {boot:?} is not listed")))?),
+    };
+    writeln!(code, "pub const BOOT_ASSET: Option<usize> = {boot_index:?};").unwrap();
+    
+    fs::write(Path::new(&env::var("OUT_DIR").unwrap()).join("assets.rs"), code)
 }
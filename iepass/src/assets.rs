@@ -0,0 +1,47 @@
+use std::fmt::Write;
+use esp_idf_svc::nvs::{EspDefaultNvsPartition, EspNvs};
+use esp_idf_svc::sys::EspError;
+use iepass_core::smol;
+
+pub struct Asset {
+    pub name: &'static str,
+    pub data: &'static [u8],
+}
+
+impl Asset {
+    // Title, author and length from the video's metadata, the manifest name if it has none
+    pub fn describe(&self) -> String {
+        let mut data = self.data;
+        let metadata = smol::Header::read(&mut data).ok().and_then(|header| header.metadata(data).ok().flatten());
+        let Some(metadata) = metadata else { return self.name.to_string() };
+        
+        let mut text = metadata.title().unwrap_or(self.name).to_string();
+        if let Some(author) = metadata.author() {
+            write!(text, " by {author}").unwrap();
+        }
+        if let Some(duration) = metadata.duration_ms() {
+            write!(text, " ({}:{:02})", duration / 60_000, duration / 1000 % 60).unwrap();
+        }
+        text
+    }
+}
+
+// ASSETS, DEFAULT_ASSET and BOOT_ASSET, generated by build.rs from assets/assets.toml
+include!(concat!(env!("OUT_DIR"), "/assets.rs"));
+
+const NAMESPACE: &str = "assets";
+
+// The selection is stored by name, so reordering the manifest doesn't change it. Falls back to the
+// manifest default if nothing was picked yet or the picked asset is no longer built in.
+pub fn load_selected(nvs: EspDefaultNvsPartition) -> Result<usize, EspError> {
+    let storage = EspNvs::new(nvs, NAMESPACE, true)?;
+    let mut buf = [0; 32];
+    let name = storage.get_str("selected", &mut buf)?;
+    
+    Ok(name.and_then(|name| ASSETS.iter().position(|asset| asset.name == name)).unwrap_or(DEFAULT_ASSET))
+}
+
+pub fn save_selected(nvs: EspDefaultNvsPartition, index: usize) -> Result<(), EspError> {
+    let mut storage = EspNvs::new(nvs, NAMESPACE, true)?;
+    storage.set_str("selected", ASSETS[index].name)
+}
@@ -0,0 +1,12 @@
+use std::io::Write;
+use iepass_core::binlog::{self, Tag};
+
+// Writes a compact binary record to the console, decode it on the host with `cargo make binlog-decode`
+pub fn log(tag: &Tag, args: &[u32]) {
+    let mut record = [0; binlog::MAX_RECORD_LEN];
+    let len = binlog::encode(tag, args, &mut record);
+    
+    // Records never contain CR or LF, so the console's newline translation passes them through untouched.
+    // Logging must never take the firmware down.
+    let _ = std::io::stdout().write_all(&record[..len]);
+}
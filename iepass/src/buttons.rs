@@ -0,0 +1,53 @@
+use esp_idf_svc::hal::gpio::{Gpio1, Gpio11, Gpio12, Gpio13, Gpio14, Gpio19, Input, PinDriver, Pull};
+use esp_idf_svc::hal::delay::FreeRtos;
+use esp_idf_svc::sys::EspError;
+
+use crate::debounce::Debounce;
+
+const PULL_SETTLE_MS: u32 = 2;
+
+pub struct Buttons {
+    pub select: Debounce<'static, Gpio1, Input>,
+    pub start: Debounce<'static, Gpio19, Input>,
+    pub a: Debounce<'static, Gpio14, Input>,
+    pub b: Debounce<'static, Gpio13, Input>,
+    pub x: Debounce<'static, Gpio12, Input>,
+    pub y: Debounce<'static, Gpio11, Input>,
+}
+
+impl Buttons {
+    pub fn new(select: Gpio1, start: Gpio19, a: Gpio14, b: Gpio13, x: Gpio12, y: Gpio11) -> Result<Self, EspError> {
+        let mut buttons = Self {
+            select: Debounce::new(PinDriver::input(select)?).with_pull(Pull::Up)?,
+            start: Debounce::new(PinDriver::input(start)?).with_pull(Pull::Up)?,
+            a: Debounce::new(PinDriver::input(a)?).with_pull(Pull::Up)?,
+            b: Debounce::new(PinDriver::input(b)?).with_pull(Pull::Up)?,
+            x: Debounce::new(PinDriver::input(x)?).with_pull(Pull::Up)?,
+            y: Debounce::new(PinDriver::input(y)?).with_pull(Pull::Up)?,
+        };
+        
+        // Debounce::new sampled the pins before the pull-ups were on, so a floating pin could read as held.
+        // Buttons held during boot pick safe mode and the like, they have to be read from settled pins.
+        FreeRtos::delay_ms(PULL_SETTLE_MS);
+        buttons.select.resync();
+        buttons.start.resync();
+        buttons.a.resync();
+        buttons.b.resync();
+        buttons.x.resync();
+        buttons.y.resync();
+        
+        Ok(buttons)
+    }
+    
+    // Falling edges of all buttons in a fixed order, so every button is polled each call
+    pub fn falling_edges(&mut self) -> [(&'static str, bool); 6] {
+        [
+            ("select", self.select.falling_edge()),
+            ("start", self.start.falling_edge()),
+            ("a", self.a.falling_edge()),
+            ("b", self.b.falling_edge()),
+            ("x", self.x.falling_edge()),
+            ("y", self.y.falling_edge()),
+        ]
+    }
+}
@@ -0,0 +1,108 @@
+use embedded_graphics_core::prelude::*;
+use embedded_graphics_core::pixelcolor::Rgb565;
+use embedded_graphics_core::primitives::Rectangle;
+use esp_idf_svc::hal::delay::FreeRtos;
+use esp_idf_svc::hal::reset;
+use esp_idf_svc::nvs::EspDefaultNvsPartition;
+
+use crate::error::{Context, Error, Subsystem};
+use crate::buttons::Buttons;
+use crate::display::{Display, DisplayConfig};
+
+#[derive(Debug, Clone, Copy)]
+enum Field {
+    OffsetX,
+    OffsetY,
+    Rgb,
+    Inverted,
+}
+
+const FIELDS: [Field; 4] = [Field::OffsetX, Field::OffsetY, Field::Rgb, Field::Inverted];
+
+fn draw_pattern(display: &mut Display) -> Result<(), Error> {
+    let Size { width, height } = display.bounding_box().size;
+    
+    display.clear(Rgb565::BLACK).map_err(|_| Error::display("clear the screen"))?;
+    
+    // 1px border, a misaligned panel shows it cut off on one side and garbage on the other
+    for rect in [
+        Rectangle::new(Point::new(0, 0), Size::new(width, 1)),
+        Rectangle::new(Point::new(0, height as i32 - 1), Size::new(width, 1)),
+        Rectangle::new(Point::new(0, 0), Size::new(1, height)),
+        Rectangle::new(Point::new(width as i32 - 1, 0), Size::new(1, height)),
+    ] {
+        display.fill_solid(&rect, Rgb565::WHITE).map_err(|_| Error::display("draw the calibration pattern"))?;
+    }
+    
+    // Primaries in the corners to check the rgb/inverted flags
+    for (x, y, color) in [
+        (2, 2, Rgb565::RED),
+        (width as i32 - 10, 2, Rgb565::GREEN),
+        (2, height as i32 - 10, Rgb565::BLUE),
+        (width as i32 - 10, height as i32 - 10, Rgb565::WHITE),
+    ] {
+        display.fill_solid(&Rectangle::new(Point::new(x, y), Size::new(8, 8)), color).map_err(|_| Error::display("draw the calibration pattern"))?;
+    }
+    
+    Ok(())
+}
+
+// Select cycles fields, A/B change the current one, Start saves and Y cancels.
+// Offsets apply immediately, rgb/inverted need a display re-init so the device restarts after saving them.
+pub fn run(display: &mut Display, buttons: &mut Buttons, nvs: EspDefaultNvsPartition, original: DisplayConfig) -> Result<(), Error> {
+    let mut config = original;
+    let mut field = 0;
+    let mut dirty = true;
+    
+    loop {
+        if dirty {
+            display.set_offset(config.offset_x, config.offset_y);
+            draw_pattern(display)?;
+            log::info!("{:?} selected, {:?}", FIELDS[field], config);
+            dirty = false;
+        }
+        
+        FreeRtos::delay_ms(10);
+        
+        if buttons.select.falling_edge() {
+            field = (field + 1) % FIELDS.len();
+            dirty = true;
+        }
+        
+        let delta: i32 = if buttons.a.falling_edge() {
+            1
+        } else if buttons.b.falling_edge() {
+            -1
+        } else {
+            0
+        };
+        
+        if delta != 0 {
+            match FIELDS[field] {
+                Field::OffsetX => config.offset_x = config.offset_x.saturating_add_signed(delta as i16).min(32),
+                Field::OffsetY => config.offset_y = config.offset_y.saturating_add_signed(delta as i16).min(32),
+                Field::Rgb => config.rgb = !config.rgb,
+                Field::Inverted => config.inverted = !config.inverted,
+            }
+            dirty = true;
+        }
+        
+        if buttons.y.falling_edge() {
+            log::info!("Calibration cancelled");
+            display.set_offset(original.offset_x, original.offset_y);
+            return Ok(());
+        }
+        
+        if buttons.start.falling_edge() {
+            config.save(nvs).context(Subsystem::Storage, "save the display config")?;
+            log::info!("Calibration saved: {config:?}");
+            
+            if config.rgb != original.rgb || config.inverted != original.inverted {
+                log::info!("Restarting to apply color settings");
+                reset::restart();
+            }
+            
+            return Ok(());
+        }
+    }
+}
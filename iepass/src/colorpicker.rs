@@ -0,0 +1,85 @@
+use embedded_graphics_core::pixelcolor::raw::RawU16;
+use embedded_graphics_core::prelude::*;
+use embedded_graphics_core::pixelcolor::Rgb565;
+use embedded_graphics_core::primitives::Rectangle;
+use esp_idf_svc::hal::delay::FreeRtos;
+
+use crate::error::Error;
+use crate::buttons::Buttons;
+
+const SWATCH_HEIGHT: u32 = 80;
+const CHANNEL_HEIGHT: u32 = 16;
+const MARKER_HEIGHT: u32 = 4;
+
+// A/B nudge the selected channel, X/Y switch channels, Start exits
+pub fn run<D>(display: &mut D, buttons: &mut Buttons) -> Result<(), Error>
+    where D: DrawTarget<Color = Rgb565> {
+    let width = display.bounding_box().size.width;
+    let max = [Rgb565::MAX_R, Rgb565::MAX_G, Rgb565::MAX_B];
+    let mut channels = [Rgb565::MAX_R / 2, Rgb565::MAX_G / 2, Rgb565::MAX_B / 2];
+    let mut selected = 0;
+    
+    display.clear(Rgb565::BLACK).map_err(|_| Error::display("clear the screen"))?;
+    
+    for (channel, &max) in max.iter().enumerate() {
+        let y = SWATCH_HEIGHT + channel as u32 * CHANNEL_HEIGHT;
+        
+        for x in 0..width {
+            let mut levels = [0; 3];
+            levels[channel] = (x * max as u32 / (width - 1)) as u8;
+            
+            display.fill_solid(
+                &Rectangle::new(Point::new(x as i32, y as i32), Size::new(1, CHANNEL_HEIGHT - MARKER_HEIGHT)),
+                Rgb565::new(levels[0], levels[1], levels[2]),
+            ).map_err(|_| Error::display("draw the color picker"))?;
+        }
+    }
+    
+    let mut dirty = true;
+    
+    loop {
+        if dirty {
+            let color = Rgb565::new(channels[0], channels[1], channels[2]);
+            log::info!("R {:2} G {:2} B {:2} = {:#06x}", channels[0], channels[1], channels[2], RawU16::from(color).into_inner());
+            
+            display.fill_solid(&Rectangle::new(Point::zero(), Size::new(width, SWATCH_HEIGHT)), color)
+                .map_err(|_| Error::display("draw the color picker"))?;
+            
+            for (channel, &max) in max.iter().enumerate() {
+                let y = SWATCH_HEIGHT + (channel as u32 + 1) * CHANNEL_HEIGHT - MARKER_HEIGHT;
+                let x = channels[channel] as u32 * (width - 3) / max as u32;
+                
+                display.fill_solid(&Rectangle::new(Point::new(0, y as i32), Size::new(width, MARKER_HEIGHT)), Rgb565::BLACK)
+                    .map_err(|_| Error::display("draw the color picker"))?;
+                display.fill_solid(
+                    &Rectangle::new(Point::new(x as i32, y as i32), Size::new(3, MARKER_HEIGHT)),
+                    if channel == selected { Rgb565::YELLOW } else { Rgb565::WHITE },
+                ).map_err(|_| Error::display("draw the color picker"))?;
+            }
+            
+            dirty = false;
+        }
+        
+        FreeRtos::delay_ms(10);
+        
+        if buttons.start.falling_edge() {
+            return Ok(());
+        }
+        if buttons.a.falling_edge() && channels[selected] < max[selected] {
+            channels[selected] += 1;
+            dirty = true;
+        }
+        if buttons.b.falling_edge() && channels[selected] > 0 {
+            channels[selected] -= 1;
+            dirty = true;
+        }
+        if buttons.x.falling_edge() {
+            selected = (selected + 2) % 3;
+            dirty = true;
+        }
+        if buttons.y.falling_edge() {
+            selected = (selected + 1) % 3;
+            dirty = true;
+        }
+    }
+}
@@ -2,6 +2,8 @@ use std::time::{Duration, Instant};
 use esp_idf_svc::hal::gpio::{InputMode, InputPin, OutputPin, Pin, PinDriver, Pull};
 use esp_idf_svc::sys::EspError;
 
+use crate::metrics;
+
 pub struct Debounce<'d, T, Mode>
 	where T: Pin,
 	      Mode: InputMode {
@@ -35,6 +37,12 @@ impl<'d, T, Mode> Debounce<'d, T, Mode>
 		Ok(self)
 	}
 	
+	// Takes the pin's current level as settled, eg. once a pull set after `new` had time to take effect
+	pub fn resync(&mut self) {
+		self.last_is_high = self.inner.is_high();
+		self.last_change = Instant::now();
+	}
+	
 	pub fn raising_edge(&mut self) -> bool {
 		let changed = self.update();
 		
@@ -67,6 +75,10 @@ impl<'d, T, Mode> Debounce<'d, T, Mode>
 				self.last_is_high = current_value;
 				self.last_change = Instant::now();
 				
+				if !current_value {
+					metrics::BUTTON_PRESSES.increment();
+				}
+				
 				return true;
 			}
 		}
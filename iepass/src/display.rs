@@ -0,0 +1,58 @@
+use st7735_lcd::ST7735;
+use esp_idf_svc::hal::gpio::{Gpio41, Gpio42, Output, PinDriver};
+use esp_idf_svc::hal::spi::{SpiDeviceDriver, SpiDriver};
+use esp_idf_svc::nvs::{EspDefaultNvsPartition, EspNvs};
+use esp_idf_svc::sys::EspError;
+
+pub type Display = ST7735<
+    SpiDeviceDriver<'static, SpiDriver<'static>>,
+    PinDriver<'static, Gpio41, Output>,
+    PinDriver<'static, Gpio42, Output>,
+>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DisplayConfig {
+    pub offset_x: u16,
+    pub offset_y: u16,
+    pub rgb: bool,
+    pub inverted: bool,
+}
+
+impl Default for DisplayConfig {
+    fn default() -> Self {
+        // What the panels we've had so far needed
+        Self {
+            offset_x: 1,
+            offset_y: 2,
+            rgb: true,
+            inverted: false,
+        }
+    }
+}
+
+impl DisplayConfig {
+    const NAMESPACE: &'static str = "display";
+    
+    pub fn load(nvs: EspDefaultNvsPartition) -> Result<Self, EspError> {
+        let storage = EspNvs::new(nvs, Self::NAMESPACE, true)?;
+        let default = Self::default();
+        
+        Ok(Self {
+            offset_x: storage.get_u16("offset_x")?.unwrap_or(default.offset_x),
+            offset_y: storage.get_u16("offset_y")?.unwrap_or(default.offset_y),
+            rgb: storage.get_u8("rgb")?.map_or(default.rgb, |rgb| rgb != 0),
+            inverted: storage.get_u8("inverted")?.map_or(default.inverted, |inverted| inverted != 0),
+        })
+    }
+    
+    pub fn save(&self, nvs: EspDefaultNvsPartition) -> Result<(), EspError> {
+        let mut storage = EspNvs::new(nvs, Self::NAMESPACE, true)?;
+        
+        storage.set_u16("offset_x", self.offset_x)?;
+        storage.set_u16("offset_y", self.offset_y)?;
+        storage.set_u8("rgb", self.rgb as u8)?;
+        storage.set_u8("inverted", self.inverted as u8)?;
+        
+        Ok(())
+    }
+}
@@ -0,0 +1,69 @@
+use std::ops::{Deref, DerefMut};
+use std::ptr::NonNull;
+use esp_idf_svc::sys::{heap_caps_aligned_alloc, heap_caps_free, MALLOC_CAP_DMA, MALLOC_CAP_INTERNAL, SOC_DMA_HIGH, SOC_DMA_LOW};
+use thiserror::Error;
+
+// GDMA wants word aligned buffers, cache line alignment keeps them from sharing a line with anything else
+const ALIGN: usize = 32;
+
+// Same range check as esp_ptr_dma_capable, which is a static inline and so not in the bindings, plus
+// the word alignment GDMA needs. PSRAM falls outside the range, so this also rules out external RAM.
+pub fn is_dma_capable<T>(buffer: &[T]) -> bool {
+    let start = buffer.as_ptr() as usize;
+    let end = start + size_of_val(buffer);
+    
+    start.is_multiple_of(4) && start >= SOC_DMA_LOW as usize && end <= SOC_DMA_HIGH as usize
+}
+
+// Fixed size buffer in internal, DMA-capable RAM, for handing straight to SPI/I2S DMA. The S3's GDMA
+// can reach PSRAM too, but only with cache line aligned buffers written back from the cache first,
+// internal RAM needs none of that.
+pub struct DmaBuffer<T: Copy> {
+    ptr: NonNull<T>,
+    len: usize,
+}
+
+impl<T: Copy> DmaBuffer<T> {
+    pub fn new(len: usize, value: T) -> Result<Self, DmaAllocError> {
+        let size = len * size_of::<T>();
+        let ptr = unsafe {
+            heap_caps_aligned_alloc(ALIGN.max(align_of::<T>()), size.max(1), MALLOC_CAP_DMA | MALLOC_CAP_INTERNAL)
+        };
+        let ptr = NonNull::new(ptr as *mut T).ok_or(DmaAllocError(size))?;
+        
+        for i in 0..len {
+            unsafe { ptr.add(i).write(value) };
+        }
+        
+        let buffer = Self { ptr, len };
+        debug_assert!(is_dma_capable(&buffer), "heap_caps_aligned_alloc returned memory DMA can't reach");
+        Ok(buffer)
+    }
+}
+
+impl<T: Copy> Deref for DmaBuffer<T> {
+    type Target = [T];
+    
+    fn deref(&self) -> &[T] {
+        unsafe { std::slice::from_raw_parts(self.ptr.as_ptr(), self.len) }
+    }
+}
+
+impl<T: Copy> DerefMut for DmaBuffer<T> {
+    fn deref_mut(&mut self) -> &mut [T] {
+        unsafe { std::slice::from_raw_parts_mut(self.ptr.as_ptr(), self.len) }
+    }
+}
+
+impl<T: Copy> Drop for DmaBuffer<T> {
+    fn drop(&mut self) {
+        unsafe { heap_caps_free(self.ptr.as_ptr() as *mut _) };
+    }
+}
+
+unsafe impl<T: Copy + Send> Send for DmaBuffer<T> {}
+unsafe impl<T: Copy + Sync> Sync for DmaBuffer<T> {}
+
+#[derive(Error, Debug)]
+#[error("Failed to allocate {0} bytes of DMA-capable memory")]
+pub struct DmaAllocError(usize);
@@ -0,0 +1,71 @@
+use std::fmt;
+use thiserror::Error;
+
+pub type Cause = Box<dyn std::error::Error + Send + Sync>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Subsystem {
+    Display,
+    Input,
+    Storage,
+    Memory,
+    System,
+    Video,
+}
+
+// What failed, while doing what, and why if the failing call told us. The display driver errors are `()`,
+// so those only carry the operation.
+#[derive(Error, Debug)]
+#[error("{subsystem}: failed to {operation}")]
+pub struct Error {
+    pub subsystem: Subsystem,
+    pub operation: &'static str,
+    #[source]
+    pub cause: Option<Cause>,
+}
+
+impl Error {
+    pub fn new(subsystem: Subsystem, operation: &'static str) -> Self {
+        Self { subsystem, operation, cause: None }
+    }
+    
+    pub fn display(operation: &'static str) -> Self {
+        Self::new(Subsystem::Display, operation)
+    }
+    
+    pub fn with_cause(mut self, cause: impl Into<Cause>) -> Self {
+        self.cause = Some(cause.into());
+        self
+    }
+    
+    // One key=value line, so crash logs can be grepped and parsed
+    pub fn log(&self) {
+        match &self.cause {
+            Some(cause) => log::error!("error subsystem={} operation=\"{}\" cause=\"{cause}\"", self.subsystem, self.operation),
+            None => log::error!("error subsystem={} operation=\"{}\"", self.subsystem, self.operation),
+        }
+    }
+}
+
+impl fmt::Display for Subsystem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Subsystem::Display => "display",
+            Subsystem::Input => "input",
+            Subsystem::Storage => "storage",
+            Subsystem::Memory => "memory",
+            Subsystem::System => "system",
+            Subsystem::Video => "video",
+        })
+    }
+}
+
+pub trait Context<T> {
+    fn context(self, subsystem: Subsystem, operation: &'static str) -> Result<T, Error>;
+}
+
+impl<T, E: Into<Cause>> Context<T> for Result<T, E> {
+    fn context(self, subsystem: Subsystem, operation: &'static str) -> Result<T, Error> {
+        self.map_err(|err| Error::new(subsystem, operation).with_cause(err))
+    }
+}
@@ -1,9 +1,9 @@
 #![feature(try_blocks)]
 
 use std::time::Instant;
-use iepass_core::rle;
-use thiserror::Error;
-use embedded_io::{Read, ReadExactError};
+use iepass_core::{blend, rle, roi, smol};
+use iepass_core::pacing::{FrameGovernor, Phase, PhaseTimer};
+use iepass_core::binlog::tags;
 use st7735_lcd::{Orientation, ST7735};
 use embedded_graphics_core::pixelcolor::raw::RawU16;
 use embedded_graphics_core::prelude::*;
@@ -11,41 +11,78 @@ use embedded_graphics_core::pixelcolor::Rgb565;
 use embedded_graphics_core::primitives::Rectangle;
 use esp_idf_svc::hal::prelude::*;
 use esp_idf_svc::hal::delay::FreeRtos;
-use esp_idf_svc::hal::gpio::{Gpio0, PinDriver, Pull};
+use esp_idf_svc::hal::gpio::{Gpio0, PinDriver};
 use esp_idf_svc::hal::spi::{config, Dma, SpiConfig, SpiDeviceDriver};
 use esp_idf_svc::hal::spi::config::DriverConfig;
+use esp_idf_svc::nvs::EspDefaultNvsPartition;
 
+mod assets;
+mod binlog;
+mod buttons;
+mod calibration;
+mod colorpicker;
 mod debounce;
+mod display;
+mod dma;
+mod error;
+mod metrics;
+mod profile;
+mod rowstream;
+mod selftest;
+mod splash;
+mod sysinfo;
 
-use debounce::Debounce;
+use buttons::Buttons;
+use display::DisplayConfig;
+use error::{Context, Error, Subsystem};
+use profile::Profile;
+use rowstream::RowStream;
+use splash::Splash;
+use sysinfo::SystemInfo;
 
-#[cfg(feature = "bad-apple")] static VIDEO: &[u8] = include_bytes!("../../assets/BadApple.smol");
-#[cfg(not(feature = "bad-apple"))] static VIDEO: &[u8] = include_bytes!("../../assets/XD.smol");
+const SEEK_SECONDS: u32 = 5;
+// Cross-faded videos are shown at about this rate, a full frame over SPI doesn't keep up with the panel's 60 Hz
+const BLEND_FPS: u8 = 30;
 
-fn main() -> Result<(), Box<dyn std::error::Error>> {
+fn main() -> Result<(), Error> {
     // It is necessary to call this function once. Otherwise, some patches to the runtime
     // implemented by esp-idf-sys might not link properly. See https://github.com/esp-rs/esp-idf-template/issues/71
     esp_idf_svc::sys::link_patches();
     // Bind the log crate to the ESP Logging facilities
     esp_idf_svc::log::EspLogger::initialize_default();
     
+    run().inspect_err(Error::log)
+}
+
+fn run() -> Result<(), Error> {
     let peripherals = Peripherals::take().unwrap();
+    let nvs = EspDefaultNvsPartition::take().context(Subsystem::Storage, "take the NVS partition")?;
+    let profile = Profile::detect();
     
-    let mut select_btn = Debounce::new(PinDriver::input(peripherals.pins.gpio1)?).with_pull(Pull::Up)?;
-    let mut start_btn = Debounce::new(PinDriver::input(peripherals.pins.gpio19)?).with_pull(Pull::Up)?;
-    let mut a_btn = Debounce::new(PinDriver::input(peripherals.pins.gpio14)?).with_pull(Pull::Up)?;
-    let mut b_btn = Debounce::new(PinDriver::input(peripherals.pins.gpio13)?).with_pull(Pull::Up)?;
-    let mut x_btn = Debounce::new(PinDriver::input(peripherals.pins.gpio12)?).with_pull(Pull::Up)?;
-    let mut y_btn = Debounce::new(PinDriver::input(peripherals.pins.gpio11)?).with_pull(Pull::Up)?;
+    let mut buttons = Buttons::new(
+        peripherals.pins.gpio1,
+        peripherals.pins.gpio19,
+        peripherals.pins.gpio14,
+        peripherals.pins.gpio13,
+        peripherals.pins.gpio12,
+        peripherals.pins.gpio11,
+    ).context(Subsystem::Input, "set up the buttons")?;
+    
+    // Hold Select during boot to ignore stored settings, in case they made the device unusable
+    let safe_mode = buttons.select.is_low();
+    if safe_mode {
+        log::warn!("Safe mode: using default settings");
+    }
     
+    let display_config = if safe_mode { DisplayConfig::default() } else { DisplayConfig::load(nvs.clone()).context(Subsystem::Storage, "load the display config")? };
     let mut display = {
-        let rgb = true;
-        let inverted = false;
+        let rgb = display_config.rgb;
+        let inverted = display_config.inverted;
         let width = 160;
         let height = 128;
         
-        let rst = PinDriver::output(peripherals.pins.gpio42)?;
-        let a0 = PinDriver::output(peripherals.pins.gpio41)?;
+        let rst = PinDriver::output(peripherals.pins.gpio42).context(Subsystem::Display, "set up the reset pin")?;
+        let a0 = PinDriver::output(peripherals.pins.gpio41).context(Subsystem::Display, "set up the A0 pin")?;
         let sda = peripherals.pins.gpio40;
         let sck = peripherals.pins.gpio39;
         
@@ -56,132 +93,315 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
             None::<Gpio0>,
             None::<Gpio0>,
             &DriverConfig {
-                dma: Dma::Auto(128 * 160 * 2),
+                dma: Dma::Auto(profile.spi_dma_len()),
                 intr_flags: Default::default(),
             },
             &SpiConfig::new().baudrate(30.MHz().into())
-        )?;
+        ).context(Subsystem::Display, "set up SPI")?;
         
         ST7735::new(spi, a0, rst, rgb, inverted, width, height)
     };
     
-    display.init(&mut FreeRtos).map_err(|_| DisplayError::InitError)?;
-    display.set_orientation(&Orientation::Landscape).map_err(|_| DisplayError::SetOrientationError)?;
-    display.set_offset(1, 2); // No idea why its needed
-    display.clear(Rgb565::MAGENTA).map_err(|_| DisplayError::ClearError)?;
-
-    log::info!("Hello, world!");
+    display.init(&mut FreeRtos).map_err(|_| Error::display("initialize"))?;
+    display.set_orientation(&Orientation::Landscape).map_err(|_| Error::display("set the orientation"))?;
+    display.set_offset(display_config.offset_x, display_config.offset_y);
+    
+    let animation = assets::BOOT_ASSET.and_then(|boot| {
+        RowStream::new(assets::ASSETS[boot].data, display.bounding_box().size)
+            .inspect_err(Error::log)
+            .ok()
+    });
+    let mut splash = Splash::show(&mut display, 3, animation)?;
+    splash.step(&mut display, "Display")?;
+    
+    SystemInfo::read().context(Subsystem::System, "read system info")?.log();
+    splash.step(&mut display, "System info")?;
     
-    let mut framebuffer = vec![0; 128 * 160];
+    // The low memory profile streams videos to the panel instead
+    let mut framebuffer = if profile.low_memory {
+        None
+    } else {
+        // Internal RAM, so the pixels can go out over SPI DMA without a trip through PSRAM
+        Some(dma::DmaBuffer::new(128 * 160, 0u16).context(Subsystem::Memory, "allocate the framebuffer")?)
+    };
+    splash.step(&mut display, "Framebuffer")?;
+    // Init is done, the boot animation stops wherever it is
+    drop(splash);
+    
+    // Hold A and B during boot to run the hardware self-test
+    if buttons.a.is_low() && buttons.b.is_low() {
+        selftest::run(&mut display, &mut buttons, nvs.clone())?;
+    }
+    
+    // Hold Y during boot to calibrate the panel offset and color flags, safe mode goes there directly
+    if safe_mode || buttons.y.is_low() {
+        calibration::run(&mut display, &mut buttons, nvs.clone(), display_config)?;
+    }
+    
+    // Hold X during boot to open the color picker
+    if buttons.x.is_low() {
+        colorpicker::run(&mut display, &mut buttons)?;
+    }
+    
+    // X cycles through the built in videos, the pick is remembered across reboots
+    let mut asset = if safe_mode {
+        assets::DEFAULT_ASSET
+    } else {
+        assets::load_selected(nvs.clone()).context(Subsystem::Storage, "load the selected video")?
+    };
+    log::info!("Video: {}", assets::ASSETS[asset].describe());
+    
+    // Y toggles cross-fading between the frames of low FPS videos
+    let mut blending = false;
+    
+    display.clear(Rgb565::MAGENTA).map_err(|_| Error::display("clear the screen"))?;
+    log::info!("Hello, world!");
     
     loop {
         FreeRtos::delay_ms(10);
         
-        if select_btn.falling_edge() {
+        if buttons.select.falling_edge() {
             log::info!("select");
-            display.clear(Rgb565::MAGENTA).map_err(|_| DisplayError::ClearError)?;
+            metrics::log();
+            display.clear(Rgb565::MAGENTA).map_err(|_| Error::display("clear the screen"))?;
             display.fill_solid(
                 &Rectangle::new(Point::new(0, 0), Size::new(160, 128)),
                 Rgb565::MAGENTA,
-            ).map_err(|_| DisplayError::DrawError)?;
+            ).map_err(|_| Error::display("fill the screen"))?;
         }
-        if start_btn.falling_edge() {
+        if buttons.start.falling_edge() {
             log::info!("start");
             
+            let mut data = assets::ASSETS[asset].data;
+            let header = smol::Header::read(&mut data).context(Subsystem::Video, "read the video header")?;
+            let (width, height) = (header.width as usize, header.height as usize);
+            log::info!("{width}x{height} @ {} FPS, {} frames, {:?}", header.fps, header.frame_count, header.codec);
+            let (data, crcs) = header.split_frames(data).context(Subsystem::Video, "find the CRC table")?;
+            
+            // Hold Select while pressing Start to check every frame against its CRC and stop at the first bad one
+            let strict = buttons.select.is_low();
+            if strict && crcs.is_none() {
+                log::warn!("Strict playback: video has no CRCs, nothing to check");
+            }
+            
+            if width == 0 || height == 0 || width > 160 || height > 128 {
+                log::error!("Video does not fit the screen");
+                continue;
+            }
+            // The seek index needs the video runs back to back, and there is no I2S output for the audio yet
+            if header.audio.is_some() {
+                log::error!("Videos with an audio track can't be played yet");
+                continue;
+            }
+            
+            // Without a framebuffer only plain RLE videos play, a row at a time and with no seeking or blending
+            let Some(framebuffer) = framebuffer.as_mut() else {
+                match RowStream::new(assets::ASSETS[asset].data, display.bounding_box().size) {
+                    Ok(mut stream) => {
+                        display.clear(Rgb565::BLACK).map_err(|_| Error::display("clear the screen"))?;
+                        stream.play(&mut display, || buttons.start.falling_edge())?;
+                    }
+                    Err(err) => err.log(),
+                }
+                continue;
+            };
+            
+            // Smaller videos are centered, ROI frames start from a black screen like the encoder does
+            let (left, top) = ((160 - width as u16) / 2, (128 - height as u16) / 2);
+            display.clear(Rgb565::BLACK).map_err(|_| Error::display("clear the screen"))?;
+            
             let start = Instant::now();
             let mut frames = 0;
-            let mut parts = (0.0, 0.0, 0.0);
-            let mut decoder = rle::Decoder::new(VIDEO);
-            let mut row = [0; 160];
-            display.set_address_window(0, 0, 159, 127).map_err(|_| DisplayError::SetOrientationError)?;
+            // ROI frames only cover what changed, so they can't be blended
+            let blend_steps = if blending && header.codec != smol::Codec::RleRoi { blend::steps(header.fps, BLEND_FPS) } else { 1 };
+            let mut prev_frame = vec![0; if blend_steps > 1 { header.frame_len() } else { 0 }];
+            let mut blended = prev_frame.clone();
+            // Fades in from the black screen, but not across a seek
+            let mut fade = true;
+            let mut governor = FrameGovernor::new(header.fps.max(1) as u32 * blend_steps);
+            let mut timer = PhaseTimer::new("player", governor.frame_time());
+            // Plain frames are indexed once per second, delta frames at every keyframe. ROI frames only hold what
+            // changed since the previous one, so they can't seek. Plain RLE is delta with every frame a keyframe.
+            let (stride, indexed_frames, keyframe_interval) = match header.codec {
+                smol::Codec::Rle => (header.fps.max(1) as usize, header.frame_count as usize, 1),
+                smol::Codec::RleRoi => (1, 0, 1),
+                smol::Codec::RleDelta => {
+                    let interval = header.keyframe_interval as usize;
+                    (interval, header.frame_count as usize, interval)
+                }
+            };
+            let mut index = vec![rle::FramePosition::default(); indexed_frames.div_ceil(stride)];
+            let indexed = rle::index_frames(data, header.run_format, header.frame_len(), stride, &mut index);
+            let roi = header.codec == smol::Codec::RleRoi;
+            let mut pixels = vec![0; if roi { header.frame_len() } else { 0 }];
+            let mut frame_buf = vec![0; if roi { 0 } else { header.frame_len() }];
+            let mut decoder = rle::DeltaDecoder::new(
+                rle::SeekableDecoder::new(data, header.frame_len(), stride, &index[..indexed]).with_format(header.run_format),
+                &mut frame_buf,
+                keyframe_interval,
+            );
             
-            'outer: for _ in 0.. {
+            let mut frame = 0;
+            while frame < header.frame_count {
+                let now = Instant::now();
+                if buttons.start.falling_edge() {
+                    break;
+                }
+                
+                // A rewinds and B fast-forwards
+                let seek_frames = SEEK_SECONDS * header.fps as u32;
+                let target = if buttons.a.falling_edge() {
+                    Some(frame.saturating_sub(seek_frames))
+                } else if buttons.b.falling_edge() {
+                    Some(frame + seek_frames)
+                } else {
+                    None
+                };
+                if let Some(target) = target {
+                    if target < header.frame_count && decoder.seek_frame(target as usize) {
+                        frame = target;
+                        fade = false;
+                    }
+                }
+                timer.record(Phase::Input, now.elapsed());
+                
+                frame += 1;
                 frames += 1;
+                governor.frame_start(start.elapsed());
                 
                 let now = Instant::now();
-                for y in 0..128 {
-                    if start_btn.falling_edge() {
-                        break 'outer;
+                let (region, pixels) = if roi {
+                    match roi::read_frame(decoder.get_mut(), &mut pixels).context(Subsystem::Video, "decode a frame")? {
+                        None => break,
+                        Some(region) => (region, &pixels[..region.area()]),
+                    }
+                } else {
+                    if !decoder.read_frame().context(Subsystem::Video, "decode a frame")? {
+                        break;
+                    }
+                    (roi::Region::full(header.width, header.height), decoder.frame())
+                };
+                
+                if strict && crcs.is_some_and(|crcs| !crcs.check(frame as usize - 1, pixels)) {
+                    log::error!("Frame {} does not match its CRC, stopping", frame - 1);
+                    display.clear(Rgb565::RED).map_err(|_| Error::display("draw the error screen"))?;
+                    while !buttons.start.falling_edge() {
+                        FreeRtos::delay_ms(10);
+                    }
+                    break;
+                }
+                timer.record(Phase::Update, now.elapsed());
+                
+                // A blended frame is shown in steps, each one mixing in more of it over the previous frame
+                for step in 1..=blend_steps {
+                    if step > 1 {
+                        governor.frame_start(start.elapsed());
+                    }
+                    
+                    let now = Instant::now();
+                    let shown = if fade && step < blend_steps {
+                        blend::mix(&prev_frame, pixels, (step * 255 / blend_steps) as u8, &mut blended);
+                        &blended[..]
+                    } else {
+                        pixels
+                    };
+                    timer.record(Phase::Update, now.elapsed());
+                    
+                    let now = Instant::now();
+                    for (pixel, color) in framebuffer.iter_mut().zip(rle::PixelDecoder::new(shown)) {
+                        *pixel = RawU16::from(color).into_inner();
                     }
+                    timer.record(Phase::Draw, now.elapsed());
                     
-                    match decoder.read_exact(&mut row) {
-                        Err(ReadExactError::UnexpectedEof) => break 'outer,
-                        result => result?,
+                    let now = Instant::now();
+                    if !region.is_empty() {
+                        let (x, y) = (left + region.x, top + region.y);
+                        debug_assert!(dma::is_dma_capable(&framebuffer[..region.area()]));
+                        display.set_address_window(x, y, x + region.width - 1, y + region.height - 1)
+                            .map_err(|_| Error::display("draw a video frame"))?;
+                        display.write_pixels_buffered(framebuffer[..region.area()].iter().copied())
+                            .map_err(|_| Error::display("draw a video frame"))?;
                     }
+                    metrics::FRAMES_RENDERED.increment();
+                    metrics::SPI_BYTES.add(region.area() as u32 * 2);
+                    timer.record(Phase::Flush, now.elapsed());
                     
-                    for x in 0..160 {
-                        let color = row[x];
-                        framebuffer[x + y * 160] = RawU16::from(Rgb565::new(
-                            ((color as u16) * (1 << 5) / 256) as u8,
-                            ((color as u16) * (1 << 6) / 256) as u8,
-                            ((color as u16) * (1 << 5) / 256) as u8,
-                        )).into_inner();
+                    // Only the first overrun is logged in full, the rest are counted
+                    if let Some(phases) = timer.end_frame() {
+                        metrics::BUDGET_OVERRUNS.increment();
+                        if timer.overruns() == 1 {
+                            log::warn!("{} went over its {} ms budget on frame {frame}: {}", timer.scene, governor.frame_time().as_millis(),
+                                       Phase::ALL.map(|phase| format!("{} {} us", phase.name(), phases[phase as usize].as_micros())).join(", "));
+                        }
                     }
+                    
+                    let dropped = governor.stats().dropped;
+                    let sleep = governor.frame_done(start.elapsed());
+                    if governor.stats().dropped != dropped {
+                        metrics::FRAMES_DROPPED.increment();
+                        binlog::log(&tags::PLAYER_FRAME_LATE, &[frames]);
+                    }
+                    
+                    // Always yield at least a tick so the idle task gets to run
+                    FreeRtos::delay_ms((sleep.as_millis() as u32).max(1));
                 }
                 
-                parts.0 += now.elapsed().as_secs_f32();
-                let now = Instant::now();
-                
-                display.write_pixels_buffered(framebuffer.iter().copied()).map_err(|_| DisplayError::DrawError)?;
-                
-                parts.1 += now.elapsed().as_secs_f32();
-                let now = Instant::now();
-                
-                FreeRtos::delay_ms(1);
-                
-                parts.2 += now.elapsed().as_secs_f32();
+                if blend_steps > 1 {
+                    prev_frame.copy_from_slice(pixels);
+                    fade = true;
+                }
             }
             
+            let frames = frames.max(1);
             log::info!("{:.2} FPS (~{} ms)",
                        frames as f32 / start.elapsed().as_secs_f32(),
                        start.elapsed().as_millis() as u32 / frames);
             
-            log::info!("{:.2} ms | {:.2} ms | {:.2} ms",
-                       parts.0 * 1000.0 / frames as f32,
-                       parts.1 * 1000.0 / frames as f32,
-                       parts.2 * 1000.0 / frames as f32);
+            for phase in Phase::ALL {
+                metrics::PHASE_TIMES[phase as usize].set(timer.average(phase).as_micros() as u32);
+            }
+            log::info!("{}", Phase::ALL.map(|phase| format!("{} {:.2} ms", phase.name(), timer.average(phase).as_secs_f32() * 1000.0)).join(" | "));
+            if timer.overruns() > 0 {
+                log::warn!("{} went over budget on {} of {} frames", timer.scene, timer.overruns(), timer.frames());
+            }
+            
+            let stats = governor.stats();
+            binlog::log(&tags::PLAYER_DONE, &[stats.frames, stats.dropped, stats.average_busy().as_micros() as u32]);
             
             log::info!("start done");
         }
-        if a_btn.falling_edge() {
+        if buttons.a.falling_edge() {
             log::info!("a");
             display.fill_solid(
                 &Rectangle::new(Point::new(16, 128 - 48), Size::new(32, 32)),
                 Rgb565::BLUE,
-            ).map_err(|_| DisplayError::DrawError)?;
+            ).map_err(|_| Error::display("draw a button marker"))?;
         }
-        if b_btn.falling_edge() {
+        if buttons.b.falling_edge() {
             log::info!("b");
             display.fill_solid(
                 &Rectangle::new(Point::new(160 - 48, 128 - 48), Size::new(32, 32)),
                 Rgb565::BLUE,
-            ).map_err(|_| DisplayError::DrawError)?;
+            ).map_err(|_| Error::display("draw a button marker"))?;
         }
-        if x_btn.falling_edge() {
+        if buttons.x.falling_edge() {
             log::info!("x");
+            asset = (asset + 1) % assets::ASSETS.len();
+            log::info!("Video: {}", assets::ASSETS[asset].describe());
+            assets::save_selected(nvs.clone(), asset).context(Subsystem::Storage, "save the selected video")?;
             display.fill_solid(
                 &Rectangle::new(Point::new(16, 16), Size::new(32, 32)),
                 Rgb565::BLUE,
-            ).map_err(|_| DisplayError::DrawError)?;
+            ).map_err(|_| Error::display("draw a button marker"))?;
         }
-        if y_btn.falling_edge() {
+        if buttons.y.falling_edge() {
             log::info!("y");
+            blending = !blending;
+            log::info!("Frame blending {}", if blending { "on" } else { "off" });
             display.fill_solid(
                 &Rectangle::new(Point::new(160 - 48, 16), Size::new(32, 32)),
                 Rgb565::BLUE,
-            ).map_err(|_| DisplayError::DrawError)?;
+            ).map_err(|_| Error::display("draw a button marker"))?;
         }
     }
 }
-
-#[derive(Error, Debug)]
-pub enum DisplayError {
-    #[error("Failed to initialize display")]
-    InitError,
-    #[error("Failed to clear display")]
-    ClearError,
-    #[error("Failed to set orientation")]
-    SetOrientationError,
-    #[error("Failed to draw a rectangle")]
-    DrawError,
-}
@@ -0,0 +1,29 @@
+use iepass_core::metrics::{Counter, Gauge};
+
+pub static FRAMES_RENDERED: Counter = Counter::new("frames_rendered");
+pub static FRAMES_DROPPED: Counter = Counter::new("frames_dropped");
+pub static SPI_BYTES: Counter = Counter::new("spi_bytes");
+pub static BUTTON_PRESSES: Counter = Counter::new("button_presses");
+pub static BUDGET_OVERRUNS: Counter = Counter::new("budget_overruns");
+pub static FREE_HEAP: Gauge = Gauge::new("free_heap");
+// Average microseconds per frame of the last playback, indexed by pacing::Phase
+pub static PHASE_TIMES: [Gauge; 4] = [
+    Gauge::new("input_us"),
+    Gauge::new("update_us"),
+    Gauge::new("draw_us"),
+    Gauge::new("flush_us"),
+];
+
+static COUNTERS: [&Counter; 5] = [&FRAMES_RENDERED, &FRAMES_DROPPED, &SPI_BYTES, &BUTTON_PRESSES, &BUDGET_OVERRUNS];
+static GAUGES: [&Gauge; 5] = [&FREE_HEAP, &PHASE_TIMES[0], &PHASE_TIMES[1], &PHASE_TIMES[2], &PHASE_TIMES[3]];
+
+pub fn log() {
+    FREE_HEAP.set(unsafe { esp_idf_svc::sys::esp_get_free_heap_size() });
+    
+    for counter in COUNTERS {
+        log::info!("{} = {}", counter.name, counter.get());
+    }
+    for gauge in GAUGES {
+        log::info!("{} = {}", gauge.name, gauge.get());
+    }
+}
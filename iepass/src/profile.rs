@@ -0,0 +1,25 @@
+use esp_idf_svc::sys::esp_get_free_heap_size;
+
+// Free heap at boot below which the firmware runs as if built with the `low-memory` feature. The full
+// profile needs about 100 KB on top of what ESP-IDF takes, parts without PSRAM start with less.
+const LOW_MEMORY_HEAP: u32 = 160 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Profile {
+    pub low_memory: bool,
+}
+
+impl Profile {
+    pub fn detect() -> Self {
+        let free_heap = unsafe { esp_get_free_heap_size() };
+        let low_memory = cfg!(feature = "low-memory") || free_heap < LOW_MEMORY_HEAP;
+        
+        log::info!("{} KB free heap, {} memory profile", free_heap / 1024, if low_memory { "low" } else { "full" });
+        Self { low_memory }
+    }
+    
+    // Largest SPI transfer the driver keeps a DMA buffer for, a whole frame or just a few rows
+    pub fn spi_dma_len(&self) -> usize {
+        if self.low_memory { 160 * 2 * 8 } else { 128 * 160 * 2 }
+    }
+}
@@ -0,0 +1,85 @@
+use std::time::{Duration, Instant};
+use embedded_graphics_core::prelude::*;
+use embedded_graphics_core::pixelcolor::Rgb565;
+use embedded_graphics_core::primitives::Rectangle;
+use embedded_io::Read;
+use esp_idf_svc::hal::delay::FreeRtos;
+use iepass_core::{rle, smol};
+
+use crate::error::{Context, Error, Subsystem};
+
+const MAX_WIDTH: usize = 160;
+
+// Plays a video by streaming it to the panel one row at a time, with no framebuffer. Used for the
+// `boot` video from the asset manifest, which runs before the framebuffer is allocated, and by the
+// low memory profile. That only works for plain RLE frames, ROI and delta frames are built on top of
+// the previous frame.
+pub struct RowStream {
+    header: smol::Header,
+    frames: &'static [u8],
+    decoder: rle::Decoder<&'static [u8]>,
+    frame: u32,
+    origin: Point,
+}
+
+impl RowStream {
+    pub fn new(mut data: &'static [u8], screen: Size) -> Result<Self, Error> {
+        let header = smol::Header::read(&mut data).context(Subsystem::Video, "read the video header")?;
+        let (frames, _) = header.split_frames(data).context(Subsystem::Video, "find the video frames")?;
+        
+        let size = Size::new(header.width as u32, header.height as u32);
+        if header.codec != smol::Codec::Rle || header.audio.is_some() || header.frame_count == 0 || size.width as usize > MAX_WIDTH || size.width > screen.width || size.height > screen.height {
+            return Err(Error::new(Subsystem::Video, "stream the video, it has to be plain RLE without audio, have frames and fit the screen"));
+        }
+        
+        Ok(Self {
+            header,
+            frames,
+            decoder: rle::Decoder::new(frames).with_format(header.run_format),
+            frame: 0,
+            origin: Rectangle::with_center(Rectangle::new(Point::zero(), screen).center(), size).top_left,
+        })
+    }
+    
+    // Draws the next frame, starting over after the last one
+    pub fn draw_frame<D>(&mut self, display: &mut D) -> Result<(), Error>
+        where D: DrawTarget<Color = Rgb565> {
+        if self.frame >= self.header.frame_count {
+            self.decoder = rle::Decoder::new(self.frames).with_format(self.header.run_format);
+            self.frame = 0;
+        }
+        
+        let width = self.header.width as usize;
+        let mut line = [0; MAX_WIDTH];
+        for y in 0..self.header.height as i32 {
+            let mut read = 0;
+            while read < width {
+                match self.decoder.read(&mut line[read..width]).context(Subsystem::Video, "decode a video row")? {
+                    0 => return Err(Error::new(Subsystem::Video, "decode a video row, the video ends mid-frame")),
+                    count => read += count,
+                }
+            }
+            
+            let row = Rectangle::new(self.origin + Point::new(0, y), Size::new(width as u32, 1));
+            display.fill_contiguous(&row, rle::PixelDecoder::new(&line[..width]))
+                .map_err(|_| Error::display("draw a video row"))?;
+        }
+        
+        self.frame += 1;
+        Ok(())
+    }
+    
+    // Plays the rest of the current loop at the video's frame rate, or until `stop` returns true
+    pub fn play<D>(&mut self, display: &mut D, mut stop: impl FnMut() -> bool) -> Result<(), Error>
+        where D: DrawTarget<Color = Rgb565> {
+        let frame_time = Duration::from_secs(1) / self.header.fps.max(1) as u32;
+        
+        while self.frame < self.header.frame_count && !stop() {
+            let start = Instant::now();
+            self.draw_frame(display)?;
+            FreeRtos::delay_ms((frame_time.saturating_sub(start.elapsed()).as_millis() as u32).max(1));
+        }
+        
+        Ok(())
+    }
+}
@@ -0,0 +1,123 @@
+use std::time::{Duration, Instant};
+use embedded_graphics_core::prelude::*;
+use embedded_graphics_core::pixelcolor::Rgb565;
+use embedded_graphics_core::primitives::Rectangle;
+use esp_idf_svc::hal::delay::FreeRtos;
+use esp_idf_svc::nvs::{EspDefaultNvsPartition, EspNvs};
+use iepass_core::{smol, testvectors};
+
+use crate::error::{Context, Error, Subsystem};
+use crate::assets;
+use crate::buttons::Buttons;
+
+const BUTTON_TIMEOUT: Duration = Duration::from_secs(10);
+const BARS: [Rgb565; 8] = [
+    Rgb565::WHITE,
+    Rgb565::YELLOW,
+    Rgb565::CYAN,
+    Rgb565::GREEN,
+    Rgb565::MAGENTA,
+    Rgb565::RED,
+    Rgb565::BLUE,
+    Rgb565::BLACK,
+];
+
+pub fn run<D>(display: &mut D, buttons: &mut Buttons, nvs: EspDefaultNvsPartition) -> Result<bool, Error>
+    where D: DrawTarget<Color = Rgb565> {
+    log::info!("Self-test started");
+    
+    let mut results = Vec::new();
+    let bounds = display.bounding_box();
+    let bar_width = bounds.size.width / BARS.len() as u32;
+    
+    for (i, color) in BARS.into_iter().enumerate() {
+        display.fill_solid(
+            &Rectangle::new(Point::new((i as u32 * bar_width) as i32, 0), Size::new(bar_width, bounds.size.height)),
+            color,
+        ).map_err(|_| Error::display("draw the self-test"))?;
+    }
+    
+    log::info!("Check the color bars, press A if they look right or B if not");
+    let display_ok = 'wait: loop {
+        FreeRtos::delay_ms(10);
+        
+        for (name, pressed) in buttons.falling_edges() {
+            match (name, pressed) {
+                ("a", true) => break 'wait true,
+                ("b", true) => break 'wait false,
+                _ => {}
+            }
+        }
+    };
+    results.push(("display", display_ok));
+    
+    display.clear(Rgb565::BLACK).map_err(|_| Error::display("clear the screen"))?;
+    let names = buttons.falling_edges().map(|(name, _)| name);
+    let step_width = bounds.size.width / names.len() as u32;
+    
+    for (i, name) in names.into_iter().enumerate() {
+        log::info!("Press {}", name.to_uppercase());
+        
+        let step = Rectangle::new(Point::new((i as u32 * step_width) as i32, 0), Size::new(step_width, bounds.size.height));
+        display.fill_solid(&step, Rgb565::YELLOW).map_err(|_| Error::display("draw the self-test"))?;
+        
+        let start = Instant::now();
+        let ok = loop {
+            FreeRtos::delay_ms(10);
+            
+            if buttons.falling_edges()[i].1 {
+                break true;
+            }
+            if start.elapsed() > BUTTON_TIMEOUT {
+                break false;
+            }
+        };
+        
+        display.fill_solid(&step, if ok { Rgb565::GREEN } else { Rgb565::RED }).map_err(|_| Error::display("draw the self-test"))?;
+        results.push((name, ok));
+    }
+    
+    // Same codec test vectors the host tests run, catches target specific miscompiles
+    let codecs_ok = match testvectors::check() {
+        Ok(()) => true,
+        Err(name) => {
+            log::error!("Test vector \"{name}\" failed");
+            false
+        }
+    };
+    results.push(("codecs", codecs_ok));
+    
+    // Videos built with CRCs are decoded in full, catches flash corruption
+    let mut assets_ok = true;
+    for asset in assets::ASSETS {
+        let mut data = asset.data;
+        let result = smol::Header::read(&mut data).map_err(|err| err.to_string()).and_then(|header| {
+            if !header.frame_crc {
+                return Ok(());
+            }
+            let mut buf = vec![0; header.frame_len()];
+            header.verify_frames(data, &mut buf).map_err(|err| err.to_string())
+        });
+        
+        if let Err(err) = result {
+            log::error!("Asset \"{}\" is corrupt: {err}", asset.name);
+            assets_ok = false;
+        }
+    }
+    results.push(("assets", assets_ok));
+    
+    let mut storage = EspNvs::new(nvs, "selftest", true).context(Subsystem::Storage, "open the self-test results")?;
+    for (name, ok) in &results {
+        log::info!("{:<8} {}", name, if *ok { "PASS" } else { "FAIL" });
+        storage.set_u8(name, *ok as u8).context(Subsystem::Storage, "save the self-test results")?;
+    }
+    
+    let passed = results.iter().all(|(_, ok)| *ok);
+    log::info!("Self-test {}", if passed { "passed" } else { "failed" });
+    
+    FreeRtos::delay_ms(1000);
+    display.clear(if passed { Rgb565::GREEN } else { Rgb565::RED }).map_err(|_| Error::display("clear the screen"))?;
+    FreeRtos::delay_ms(2000);
+    
+    Ok(passed)
+}
@@ -0,0 +1,81 @@
+use embedded_graphics_core::prelude::*;
+use embedded_graphics_core::pixelcolor::Rgb565;
+use embedded_graphics_core::primitives::Rectangle;
+
+use crate::rowstream::RowStream;
+use crate::error::{Error, Subsystem};
+
+const BAR_SIZE: Size = Size::new(120, 8);
+
+pub struct Splash {
+    bar: Rectangle,
+    steps: u32,
+    done: u32,
+    animation: Option<RowStream>,
+}
+
+impl Splash {
+    // With a boot animation, every step shows its next frame instead of filling the progress bar
+    pub fn show<D>(display: &mut D, steps: u32, animation: Option<RowStream>) -> Result<Self, Error>
+        where D: DrawTarget<Color = Rgb565> {
+        let mut splash = Self {
+            bar: Rectangle::with_center(display.bounding_box().center(), BAR_SIZE),
+            steps: steps.max(1),
+            done: 0,
+            animation,
+        };
+        
+        display.clear(Rgb565::BLACK).map_err(|_| Error::display("clear the screen"))?;
+        if splash.animation.is_some() {
+            splash.animate(display)?;
+        } else {
+            splash.draw_bar(display)?;
+        }
+        
+        Ok(splash)
+    }
+    
+    pub fn step<D>(&mut self, display: &mut D, name: &str) -> Result<(), Error>
+        where D: DrawTarget<Color = Rgb565> {
+        self.done = (self.done + 1).min(self.steps);
+        log::info!("Boot [{}/{}] {name}", self.done, self.steps);
+        
+        self.animate(display)
+    }
+    
+    // Draws the next animation frame, or fills the bar up to the current step without one. A broken
+    // animation only costs the animation, the splash carries on with the progress bar.
+    fn animate<D>(&mut self, display: &mut D) -> Result<(), Error>
+        where D: DrawTarget<Color = Rgb565> {
+        let Some(animation) = &mut self.animation else { return self.fill_bar(display) };
+        
+        match animation.draw_frame(display) {
+            Err(err) if err.subsystem == Subsystem::Video => {
+                err.log();
+                log::warn!("Dropping the boot animation");
+                self.animation = None;
+                display.clear(Rgb565::BLACK).map_err(|_| Error::display("clear the screen"))?;
+                self.draw_bar(display)
+            }
+            result => result,
+        }
+    }
+    
+    fn draw_bar<D>(&self, display: &mut D) -> Result<(), Error>
+        where D: DrawTarget<Color = Rgb565> {
+        let center = self.bar.center();
+        display.fill_solid(&Rectangle::with_center(center, BAR_SIZE + Size::new(4, 4)), Rgb565::WHITE)
+            .map_err(|_| Error::display("draw the boot splash"))?;
+        display.fill_solid(&Rectangle::with_center(center, BAR_SIZE + Size::new(2, 2)), Rgb565::BLACK)
+            .map_err(|_| Error::display("draw the boot splash"))?;
+        
+        self.fill_bar(display)
+    }
+    
+    fn fill_bar<D>(&self, display: &mut D) -> Result<(), Error>
+        where D: DrawTarget<Color = Rgb565> {
+        let width = self.bar.size.width * self.done / self.steps;
+        display.fill_solid(&Rectangle::new(self.bar.top_left, Size::new(width, self.bar.size.height)), Rgb565::MAGENTA)
+            .map_err(|_| Error::display("draw the boot splash"))
+    }
+}
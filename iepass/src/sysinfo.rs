@@ -0,0 +1,117 @@
+use std::ffi::CStr;
+use esp_idf_svc::sys::{self, esp, EspError};
+
+pub const FIRMWARE_VERSION: &str = env!("CARGO_PKG_VERSION");
+pub const GIT_HASH: &str = env!("IEPASS_GIT_HASH");
+
+pub struct Partition {
+    pub label: String,
+    pub kind: u32,
+    pub subtype: u32,
+    pub address: u32,
+    pub size: u32,
+}
+
+pub struct SystemInfo {
+    pub chip_model: &'static str,
+    pub chip_revision: u16,
+    pub cores: u8,
+    pub flash_size: u32,
+    pub wifi_mac: [u8; 6],
+    pub bt_mac: [u8; 6],
+    pub idf_version: String,
+    pub partitions: Vec<Partition>,
+}
+
+impl SystemInfo {
+    pub fn read() -> Result<Self, EspError> {
+        let mut chip_info = sys::esp_chip_info_t::default();
+        let mut flash_size = 0;
+        let mut wifi_mac = [0; 6];
+        let mut bt_mac = [0; 6];
+        
+        unsafe {
+            sys::esp_chip_info(&mut chip_info);
+            esp!(sys::esp_flash_get_size(std::ptr::null_mut(), &mut flash_size))?;
+            esp!(sys::esp_read_mac(wifi_mac.as_mut_ptr(), sys::esp_mac_type_t_ESP_MAC_WIFI_STA))?;
+            esp!(sys::esp_read_mac(bt_mac.as_mut_ptr(), sys::esp_mac_type_t_ESP_MAC_BT))?;
+        }
+        
+        let idf_version = unsafe { CStr::from_ptr((*sys::esp_app_get_description()).idf_ver.as_ptr()) }
+            .to_string_lossy()
+            .into_owned();
+        
+        Ok(Self {
+            chip_model: chip_model_name(chip_info.model),
+            chip_revision: chip_info.revision,
+            cores: chip_info.cores,
+            flash_size,
+            wifi_mac,
+            bt_mac,
+            idf_version,
+            partitions: read_partitions(),
+        })
+    }
+    
+    pub fn log(&self) {
+        log::info!("IE Pass v{FIRMWARE_VERSION} ({GIT_HASH}), ESP-IDF {}", self.idf_version);
+        log::info!("Chip: {} rev v{}.{}, {} core(s), {} MB flash",
+                   self.chip_model,
+                   self.chip_revision / 100,
+                   self.chip_revision % 100,
+                   self.cores,
+                   self.flash_size / (1024 * 1024));
+        log::info!("WiFi MAC: {}, BT MAC: {}", format_mac(&self.wifi_mac), format_mac(&self.bt_mac));
+        
+        for partition in &self.partitions {
+            log::info!("Partition {:<16} type {:#04x}/{:#04x} at {:#08x}, {} KB",
+                       partition.label,
+                       partition.kind,
+                       partition.subtype,
+                       partition.address,
+                       partition.size / 1024);
+        }
+    }
+}
+
+fn chip_model_name(model: sys::esp_chip_model_t) -> &'static str {
+    match model {
+        sys::esp_chip_model_t_CHIP_ESP32 => "ESP32",
+        sys::esp_chip_model_t_CHIP_ESP32S2 => "ESP32-S2",
+        sys::esp_chip_model_t_CHIP_ESP32S3 => "ESP32-S3",
+        sys::esp_chip_model_t_CHIP_ESP32C3 => "ESP32-C3",
+        _ => "Unknown",
+    }
+}
+
+fn format_mac(mac: &[u8; 6]) -> String {
+    format!("{:02X}:{:02X}:{:02X}:{:02X}:{:02X}:{:02X}", mac[0], mac[1], mac[2], mac[3], mac[4], mac[5])
+}
+
+fn read_partitions() -> Vec<Partition> {
+    let mut partitions = Vec::new();
+    
+    unsafe {
+        let mut iter = sys::esp_partition_find(
+            sys::esp_partition_type_t_ESP_PARTITION_TYPE_ANY,
+            sys::esp_partition_subtype_t_ESP_PARTITION_SUBTYPE_ANY,
+            std::ptr::null(),
+        );
+        
+        while !iter.is_null() {
+            let partition = &*sys::esp_partition_get(iter);
+            
+            partitions.push(Partition {
+                label: CStr::from_ptr(partition.label.as_ptr()).to_string_lossy().into_owned(),
+                kind: partition.type_ as u32,
+                subtype: partition.subtype as u32,
+                address: partition.address,
+                size: partition.size,
+            });
+            
+            iter = sys::esp_partition_next(iter);
+        }
+    }
+    
+    partitions
+}
@@ -0,0 +1,22 @@
+//! ```cargo
+//! [dependencies]
+//! iepass-core = { path = "../iepass-core" }
+//! ```
+
+use iepass_core::binlog::RecordReader;
+
+fn main() {
+	let args: Vec<_> = std::env::args().collect();
+	
+	if let [_, input] = args.as_slice() {
+		let data = std::fs::read(input).expect("Failed to read input file");
+		let mut reader = RecordReader::new(data.as_slice());
+		
+		while let Some(record) = reader.next_record().unwrap() {
+			println!("{record}");
+		}
+	} else {
+		eprintln!("Usage: binlog_decode <captured serial log>");
+		std::process::exit(1);
+	}
+}
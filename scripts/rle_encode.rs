@@ -3,20 +3,202 @@
 //! iepass-core = { path = "../iepass-core", features = ["std"] }
 //! ```
 
-use std::fs::File;
-use iepass_core::rle;
+use std::fs::{File, OpenOptions};
+use std::io::{Read, Write};
+use iepass_core::crc32::crc32;
+use iepass_core::quantize::Quantizer;
+use iepass_core::smol::{self, Audio, AudioCodec, Codec, Header};
+use iepass_core::{rle, roi};
+
+const DEFAULT_SIZE: (u16, u16) = (160, 128);
+const DEFAULT_FPS: u8 = 30;
+const DEFAULT_SAMPLE_RATE: u16 = 8000;
+// Gray values a pixel may drift past a level boundary before it switches level
+const HYSTERESIS: u8 = 6;
+
+fn usage() -> ! {
+	eprintln!("Usage: rle_encode [--size <W>x<H>] [--fps <N>] [--roi | --delta <keyframe interval>] [--levels <2-255>] [--varint] [--crc] [--audio <u8 PCM file> [--rate <Hz>]] [--title <text>] [--author <text>] <input file> <output file>");
+	std::process::exit(1);
+}
 
 fn main() {
-	let args: Vec<_> = std::env::args().collect();
-	
-	if let [_, input, output] = args.as_slice() {
-		println!("RLE Encoding {input} -> {output}");
-		std::io::copy(
-			&mut File::open(input).expect("Failed to create output file"),
-			&mut rle::Encoder::new_std(&mut File::create(output).expect("Failed to open input file")),
-		).unwrap();
-	} else {
-		eprintln!("Usage: rle_encode <input file> <output file>");
-		std::process::exit(1);
+	let (mut width, mut height) = DEFAULT_SIZE;
+	let mut fps = DEFAULT_FPS;
+	let mut roi = false;
+	let mut delta = None;
+	let mut levels = None;
+	let mut run_format = rle::RunFormat::Classic;
+	let mut frame_crc = false;
+	let mut audio_path = None;
+	let mut sample_rate = DEFAULT_SAMPLE_RATE;
+	let mut title = None;
+	let mut author = None;
+	let mut paths = Vec::new();
+	
+	let mut args = std::env::args().skip(1);
+	while let Some(arg) = args.next() {
+		match arg.as_str() {
+			"--size" => {
+				let size = args.next().unwrap_or_else(|| usage());
+				let (w, h) = size.split_once('x').unwrap_or_else(|| usage());
+				width = w.parse().unwrap_or_else(|_| usage());
+				height = h.parse().unwrap_or_else(|_| usage());
+			}
+			"--fps" => fps = args.next().and_then(|fps| fps.parse().ok()).unwrap_or_else(|| usage()),
+			"--roi" => roi = true,
+			"--delta" => delta = Some(args.next().and_then(|interval| interval.parse().ok()).unwrap_or_else(|| usage())),
+			"--levels" => levels = Some(args.next().and_then(|levels| levels.parse().ok()).unwrap_or_else(|| usage())),
+			"--varint" => run_format = rle::RunFormat::Varint,
+			"--crc" => frame_crc = true,
+			"--audio" => audio_path = Some(args.next().unwrap_or_else(|| usage())),
+			"--rate" => sample_rate = args.next().and_then(|rate| rate.parse().ok()).unwrap_or_else(|| usage()),
+			"--title" => title = Some(args.next().unwrap_or_else(|| usage())),
+			"--author" => author = Some(args.next().unwrap_or_else(|| usage())),
+			_ => paths.push(arg),
+		}
+	}
+	
+	let [input, output] = paths.as_slice() else { usage() };
+	let codec = match (roi, delta) {
+		(false, None) => Codec::Rle,
+		(true, None) => Codec::RleRoi,
+		(false, Some(_)) => Codec::RleDelta,
+		(true, Some(_)) => usage(),
+	};
+	
+	println!("RLE Encoding {input} -> {output} ({codec:?}, {run_format:?} runs)");
+	let mut raw = Vec::new();
+	File::open(input).expect("Failed to open input file").read_to_end(&mut raw).unwrap();
+	
+	let audio = audio_path.map(|path| std::fs::read(path).expect("Failed to read audio file"));
+	let mut header = Header::new(width, height, fps, 0)
+		.with_codec(codec)
+		.with_keyframe_interval(delta.unwrap_or(1))
+		.with_run_format(run_format)
+		.with_frame_crc(frame_crc)
+		.with_metadata(true);
+	if audio.is_some() {
+		header = header.with_audio(Audio { codec: AudioCodec::Pcm8, sample_rate });
+	}
+	let frame_len = header.frame_len();
+	header.frame_count = (raw.len() / frame_len) as u32;
+	if raw.len() % frame_len != 0 {
+		eprintln!("Warning: input is not a whole number of {width}x{height} frames, dropping the last {} bytes", raw.len() % frame_len);
+	}
+	println!("{width}x{height} @ {fps} FPS, {} frames", header.frame_count);
+	
+	if let Some(levels) = levels {
+		println!("Quantizing to {levels} gray levels");
+		let quantizer = Quantizer::new(levels, HYSTERESIS);
+		let mut prev: Option<Vec<u8>> = None;
+		
+		for frame in raw.chunks_exact_mut(frame_len) {
+			quantizer.apply(frame, prev.as_deref());
+			prev = Some(frame.to_vec());
+		}
+	}
+	
+	// Header and metadata, everything before the frames
+	let duration = ((header.frame_count as u64 * 1000 / fps.max(1) as u64) as u32).to_le_bytes();
+	let mut entries = vec![smol::Entry { tag: smol::TAG_DURATION, value: &duration }];
+	entries.extend(title.as_ref().map(|title| smol::Entry { tag: smol::TAG_TITLE, value: title.as_bytes() }));
+	entries.extend(author.as_ref().map(|author| smol::Entry { tag: smol::TAG_AUTHOR, value: author.as_bytes() }));
+	let mut prefix = header.to_bytes().to_vec();
+	smol::write_metadata(&mut prefix, &entries).expect("Title or author too long");
+	
+	let mut file = File::create(output).expect("Failed to create output file");
+	file.write_all(&prefix).unwrap();
+	let mut encoder = rle::Encoder::new_std(file).with_format(run_format);
+	let mut crcs = Vec::new();
+	// With audio every frame's runs are flushed on their own, these are where each frame's video ends
+	let mut frame_ends = Vec::new();
+	
+	let stats = match codec {
+		Codec::Rle => {
+			for frame in raw.chunks_exact(frame_len) {
+				encoder.write_all(frame).unwrap();
+				if audio.is_some() {
+					encoder.flush().unwrap();
+					frame_ends.push(encoder.stats().bytes_out as usize);
+				}
+			}
+			let stats = encoder.stats();
+			encoder.finalize().unwrap();
+			crcs.extend(raw.chunks_exact(frame_len).map(crc32));
+			stats
+		}
+		Codec::RleRoi => {
+			let mut prev: &[u8] = &vec![0; frame_len];
+			let mut pixels = 0;
+			
+			for frame in raw.chunks_exact(frame_len) {
+				let region = roi::diff(prev, frame, width as usize);
+				pixels += region.area();
+				roi::write_frame(&mut encoder, frame, width as usize, region).unwrap();
+				if audio.is_some() {
+					encoder.flush().unwrap();
+					frame_ends.push(encoder.stats().bytes_out as usize);
+				}
+				
+				// Over the packed region pixels, like the player sees them
+				let mut packed = Vec::with_capacity(region.area());
+				roi::write_frame(&mut packed, frame, width as usize, region).unwrap();
+				crcs.push(crc32(&packed[8..]));
+				prev = frame;
+			}
+			let stats = encoder.stats();
+			encoder.finalize().unwrap();
+			
+			println!("Kept {:.1}% of pixels", pixels as f64 * 100.0 / raw.len().max(1) as f64);
+			stats
+		}
+		Codec::RleDelta => {
+			let mut prev = vec![0; frame_len];
+			let mut delta = rle::DeltaEncoder::new(encoder, &mut prev, header.keyframe_interval as usize);
+			
+			for frame in raw.chunks_exact(frame_len) {
+				delta.write_frame(frame).unwrap();
+				if audio.is_some() {
+					delta.flush().unwrap();
+					frame_ends.push(delta.stats().bytes_out as usize);
+				}
+			}
+			let stats = delta.stats();
+			delta.finalize().unwrap();
+			crcs.extend(raw.chunks_exact(frame_len).map(crc32));
+			stats
+		}
+	};
+	println!("{} -> {} bytes ({:.1}%), {} repeat and {} literal runs, longest {}",
+		stats.bytes_in, stats.bytes_out, stats.ratio() * 100.0, stats.repeat_runs, stats.literal_runs, stats.longest_run);
+	
+	if let Some(audio) = audio {
+		let samples_per_second = sample_rate as usize;
+		let fps = fps.max(1) as usize;
+		let video_len = header.frame_count as usize * samples_per_second / fps;
+		if audio.len() != video_len {
+			eprintln!("Warning: {} audio samples for {video_len} samples of video, the rest is cut or left silent", audio.len());
+		}
+		
+		// Rewrite the frames interleaved with the samples that play during each one
+		let file = std::fs::read(output).expect("Failed to reread output file");
+		let video = &file[prefix.len()..];
+		let mut muxed = prefix.clone();
+		let mut start = 0;
+		for (i, &end) in frame_ends.iter().enumerate() {
+			let samples = (i * samples_per_second / fps).min(audio.len())..((i + 1) * samples_per_second / fps).min(audio.len());
+			smol::write_chunk(&mut muxed, smol::Chunk { video: &video[start..end], audio: &audio[samples] }).unwrap();
+			start = end;
+		}
+		std::fs::write(output, muxed).expect("Failed to write output file");
+		println!("Interleaved {sample_rate} Hz audio");
+	}
+	
+	if frame_crc {
+		let mut file = OpenOptions::new().append(true).open(output).expect("Failed to reopen output file");
+		for crc in crcs {
+			file.write_all(&crc.to_le_bytes()).unwrap();
+		}
+		println!("Appended {} frame CRCs", header.frame_count);
 	}
 }
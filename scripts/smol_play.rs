@@ -0,0 +1,180 @@
+//! ```cargo
+//! [dependencies]
+//! iepass-core = { path = "../iepass-core", features = ["std"] }
+//! minifb = "0.28"
+//! rodio = { version = "0.20", default-features = false }
+//! ```
+
+use std::time::{Duration, Instant};
+use iepass_core::{rle, roi, smol};
+use minifb::{Key, KeyRepeat, Scale, Window, WindowOptions};
+use rodio::buffer::SamplesBuffer;
+use rodio::{OutputStream, Sink};
+
+const SEEK_SECONDS: usize = 5;
+
+// Same lookup table as the device, so banding looks exactly like on the panel
+fn to_rgb565_preview(gray: u8) -> u32 {
+	let raw = rle::GRAY_TO_RGB565[gray as usize] as u32;
+	let r = (raw >> 11) << 3;
+	let g = (raw >> 5 & 0x3F) << 2;
+	let b = (raw & 0x1F) << 3;
+	(r << 16) | (g << 8) | b
+}
+
+fn main() {
+	let mut args: Vec<_> = std::env::args().skip(1).collect();
+	let strict = args.iter().any(|arg| arg == "--strict");
+	args.retain(|arg| arg != "--strict");
+	let [input] = args.as_slice() else {
+		eprintln!("Usage: smol_play [--strict] <input file>");
+		std::process::exit(1);
+	};
+	
+	let file = std::fs::read(input).expect("Failed to open input file");
+	let mut data = &file[..];
+	let header = smol::Header::read(&mut data).unwrap();
+	let (width, height) = (header.width as usize, header.height as usize);
+	println!("{width}x{height} @ {} FPS, {} frames, {:?}", header.fps, header.frame_count, header.codec);
+	if let Some(metadata) = header.metadata(data).unwrap() {
+		println!("\"{}\" by {}, {} ms", metadata.title().unwrap_or("Untitled"), metadata.author().unwrap_or("unknown"), metadata.duration_ms().unwrap_or(0));
+	}
+	let (data, crcs) = header.split_frames(data).unwrap();
+	if strict && crcs.is_none() {
+		eprintln!("Warning: --strict on a video without CRCs");
+	}
+	// Back to back, the video chunks of an interleaved file are a plain RLE stream. The audio chunks go
+	// to the default output device, each frame's starting at the sample where its chunk does.
+	// Subtitles wait for a subtitle track in the container, .smol doesn't have one yet.
+	let mut samples = Vec::new();
+	let mut audio_starts = Vec::new();
+	let video = header.audio.map(|audio| {
+		println!("{:?} audio at {} Hz", audio.codec, audio.sample_rate);
+		let mut video = Vec::new();
+		for chunk in smol::Demuxer::new(data) {
+			let chunk = chunk.unwrap();
+			audio_starts.push(samples.len());
+			// Unsigned 8-bit PCM, the only codec so far
+			samples.extend(chunk.audio.iter().map(|&sample| (sample as i16 - 128) << 8));
+			video.extend_from_slice(chunk.video);
+		}
+		video
+	});
+	let data = video.as_deref().unwrap_or(data);
+	
+	let output = header.audio.and_then(|_| OutputStream::try_default()
+		.inspect_err(|err| eprintln!("Warning: no audio output, playing without sound: {err}"))
+		.ok());
+	let sink = output.as_ref().map(|(_, handle)| Sink::try_new(handle).expect("Failed to open audio output"));
+	// Restarts the audio from the start of `frame`
+	let queue_audio = |frame: usize, paused: bool| {
+		let (Some(sink), Some(audio)) = (&sink, header.audio) else { return };
+		let start = audio_starts.get(frame).copied().unwrap_or(samples.len());
+		sink.clear();
+		sink.append(SamplesBuffer::new(1, audio.sample_rate as u32, &samples[start..]));
+		if !paused {
+			sink.play();
+		}
+	};
+	println!("Space pauses, Left/Right seek by {SEEK_SECONDS}s, Escape quits");
+	
+	// Plain RLE is delta with every frame a keyframe, ROI streams can't seek
+	let (stride, indexed_frames, keyframe_interval) = match header.codec {
+		smol::Codec::Rle => (header.fps.max(1) as usize, header.frame_count as usize, 1),
+		smol::Codec::RleRoi => (1, 0, 1),
+		smol::Codec::RleDelta => {
+			let interval = header.keyframe_interval as usize;
+			(interval, header.frame_count as usize, interval)
+		}
+	};
+	let mut index = vec![rle::FramePosition::default(); indexed_frames.div_ceil(stride)];
+	let indexed = rle::index_frames(data, header.run_format, header.frame_len(), stride, &mut index);
+	let roi = header.codec == smol::Codec::RleRoi;
+	let mut pixels = vec![0; header.frame_len()];
+	let mut frame_buf = vec![0; header.frame_len()];
+	let mut decoder = rle::DeltaDecoder::new(
+		rle::SeekableDecoder::new(data, header.frame_len(), stride, &index[..indexed]).with_format(header.run_format),
+		&mut frame_buf,
+		keyframe_interval,
+	);
+	
+	let mut window = Window::new(input, width, height, WindowOptions { scale: Scale::X4, ..WindowOptions::default() })
+		.expect("Failed to open window");
+	// Paced from the clock below instead, minifb's limiter only spaces out updates and drifts under decode load
+	window.set_target_fps(0);
+	
+	let frame_time = Duration::from_secs(1) / header.fps.max(1) as u32;
+	let mut screen = vec![0; width * height];
+	let mut frame: usize = 0;
+	let mut paused = false;
+	// Frame clock.1 is due at clock.0, every later one a frame_time after the one before
+	let mut clock = (Instant::now(), 0);
+	// Decode one frame even while paused, after a seek
+	let mut redraw = false;
+	queue_audio(0, paused);
+	
+	while window.is_open() && !window.is_key_down(Key::Escape) {
+		if window.is_key_pressed(Key::Space, KeyRepeat::No) {
+			paused = !paused;
+			clock = (Instant::now(), frame);
+			match &sink {
+				Some(sink) if paused => sink.pause(),
+				Some(sink) => sink.play(),
+				None => {}
+			}
+		}
+		
+		let seek_frames = SEEK_SECONDS * header.fps as usize;
+		let target = if window.is_key_pressed(Key::Left, KeyRepeat::Yes) {
+			Some(frame.saturating_sub(seek_frames))
+		} else if window.is_key_pressed(Key::Right, KeyRepeat::Yes) {
+			Some(frame + seek_frames)
+		} else {
+			None
+		};
+		match target {
+			Some(target) if target < header.frame_count as usize && decoder.seek_frame(target) => {
+				frame = target;
+				clock = (Instant::now(), frame);
+				redraw = true;
+				queue_audio(frame, paused);
+			}
+			_ => {}
+		}
+		
+		// Every frame that is due gets decoded, so a slow one delays the next frames instead of the whole video
+		let due = clock.1 + (clock.0.elapsed().as_nanos() / frame_time.as_nanos()) as usize;
+		while frame < header.frame_count as usize && (redraw || (!paused && frame <= due)) {
+			let (region, pixels) = if roi {
+				let region = roi::read_frame(decoder.get_mut(), &mut pixels).unwrap().expect("Truncated stream");
+				(region, &pixels[..region.area()])
+			} else {
+				assert!(decoder.read_frame().unwrap(), "Truncated stream");
+				(roi::Region::full(header.width, header.height), decoder.frame())
+			};
+			
+			if strict && crcs.is_some_and(|crcs| !crcs.check(frame, pixels)) {
+				eprintln!("Frame {frame} does not match its CRC");
+				std::process::exit(1);
+			}
+			
+			for (row, line) in pixels.chunks_exact(region.width.max(1) as usize).enumerate() {
+				let start = (region.y as usize + row) * width + region.x as usize;
+				for (pixel, &gray) in screen[start..start + line.len()].iter_mut().zip(line) {
+					*pixel = to_rgb565_preview(gray);
+				}
+			}
+			
+			frame += 1;
+			redraw = false;
+			window.set_title(&format!("{input} - {frame}/{}", header.frame_count));
+		}
+		
+		window.update_with_buffer(&screen, width, height).unwrap();
+		
+		// Short naps, so keys stay responsive at low frame rates
+		let next = clock.0 + frame_time * (frame - clock.1) as u32;
+		let wait = if paused || frame >= header.frame_count as usize { Duration::MAX } else { next.saturating_duration_since(Instant::now()) };
+		std::thread::sleep(wait.min(Duration::from_millis(10)));
+	}
+}